@@ -0,0 +1,230 @@
+//! ACPI video extensions: display outputs and their backlight.
+//!
+//! A display adapter lists its outputs in `_DOD`; each is a child device whose `_ADR`
+//! matches one of the listed IDs. An output with a backlight reports its supported
+//! brightness levels through `_BCL`, is set through `_BCM` and read back through `_BQC`.
+//! Brightness hotkeys are delivered as notifications to the output.
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use crate::{
+    namespace::{self, integer, Handle, Object},
+    notify::Notify,
+    AE_NOT_FOUND, AE_TYPE,
+};
+
+/// Brightness notification values.
+const VIDEO_NOTIFY_CYCLE_BRIGHTNESS: u8 = 0x85;
+const VIDEO_NOTIFY_INCREASE_BRIGHTNESS: u8 = 0x86;
+const VIDEO_NOTIFY_DECREASE_BRIGHTNESS: u8 = 0x87;
+const VIDEO_NOTIFY_ZERO_BRIGHTNESS: u8 = 0x88;
+const VIDEO_NOTIFY_DISPLAY_OFF: u8 = 0x89;
+
+/// Bits of a `_DOD` entry.
+const DOD_ID_MASK: u32 = 0xFFFF;
+const DOD_TYPE_SHIFT: u32 = 8;
+const DOD_TYPE_MASK: u32 = 0xF;
+
+/// `_DOS` argument: the OS switches outputs itself and the firmware leaves the brightness
+/// alone, e.g. when switching between AC and battery.
+const DOS_OS_CONTROL: u64 = 1 << 2;
+
+/// The kind of display attached to an output, from its `_DOD` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayType {
+    Other,
+    /// VGA, CRT or VESA monitor.
+    Vga,
+    /// TV or HDTV.
+    Tv,
+    /// External digital monitor, e.g. over HDMI or DisplayPort.
+    ExternalDigital,
+    /// Internal panel, usually the one with the backlight.
+    InternalDigital,
+    Reserved(u8),
+}
+
+impl DisplayType {
+    const fn from_value(value: u8) -> Self {
+        match value {
+            0 => Self::Other,
+            1 => Self::Vga,
+            2 => Self::Tv,
+            3 => Self::ExternalDigital,
+            4 => Self::InternalDigital,
+            value => Self::Reserved(value),
+        }
+    }
+}
+
+/// A brightness hotkey, as notified to an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BrightnessEvent {
+    /// Go to the next level, wrapping around at the highest.
+    Cycle,
+    Increase,
+    Decrease,
+    /// Go to the lowest level.
+    Zero,
+    /// Turn the display off.
+    DisplayOff,
+}
+
+impl BrightnessEvent {
+    /// Returns the event for a notification, or `None` if it is not about brightness.
+    pub const fn from_notify(notify: Notify) -> Option<Self> {
+        match notify {
+            Notify::DeviceSpecific(VIDEO_NOTIFY_CYCLE_BRIGHTNESS) => Some(Self::Cycle),
+            Notify::DeviceSpecific(VIDEO_NOTIFY_INCREASE_BRIGHTNESS) => Some(Self::Increase),
+            Notify::DeviceSpecific(VIDEO_NOTIFY_DECREASE_BRIGHTNESS) => Some(Self::Decrease),
+            Notify::DeviceSpecific(VIDEO_NOTIFY_ZERO_BRIGHTNESS) => Some(Self::Zero),
+            Notify::DeviceSpecific(VIDEO_NOTIFY_DISPLAY_OFF) => Some(Self::DisplayOff),
+            _ => None,
+        }
+    }
+}
+
+/// The brightness levels of an output, from `_BCL`. Levels are in percent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrightnessLevels {
+    /// The level the firmware suggests on AC power.
+    pub ac: u32,
+    /// The level the firmware suggests on battery.
+    pub battery: u32,
+    /// The supported levels, in ascending order.
+    pub levels: Vec<u32>,
+}
+
+/// A display adapter, i.e. a device with a `_DOD` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoAdapter {
+    device: Handle,
+}
+
+impl VideoAdapter {
+    pub fn new(device: Handle) -> Self {
+        Self { device }
+    }
+
+    /// Finds all display adapters.
+    pub fn all() -> Result<Vec<Self>, ACPI_STATUS> {
+        Ok(namespace::find_objects(ACPI_TYPE_DEVICE)?
+            .into_iter()
+            .filter(|device| device.has_child("_DOD"))
+            .map(Self::new)
+            .collect())
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Evaluates `_DOD` and matches its entries to the child devices by `_ADR`.
+    ///
+    /// Entries without a matching child are left out.
+    pub fn outputs(&self) -> Result<Vec<VideoOutput>, ACPI_STATUS> {
+        let ids = self
+            .device
+            .evaluate_package("_DOD", &[])?
+            .iter()
+            .map(|element| integer(element).map(|id| id as u32))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = Vec::new();
+
+        for child in self.device.children(ACPI_TYPE_DEVICE)? {
+            let address = match child.evaluate_integer("_ADR", &[]) {
+                Ok(address) => address as u32,
+                Err(AE_NOT_FOUND) => continue,
+                Err(status) => return Err(status),
+            };
+
+            if let Some(&id) = ids
+                .iter()
+                .find(|&&id| id & DOD_ID_MASK == address & DOD_ID_MASK)
+            {
+                outputs.push(VideoOutput { device: child, id });
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Tells the firmware through `_DOS` that the OS handles output switching and
+    /// brightness, so that hotkeys are only notified instead of acted on.
+    pub fn take_control(&self) -> Result<(), ACPI_STATUS> {
+        self.device
+            .evaluate("_DOS", &[Object::Integer(DOS_OS_CONTROL)])
+            .map(|_| ())
+    }
+}
+
+/// A display output of a [`VideoAdapter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoOutput {
+    device: Handle,
+    /// The `_DOD` entry of the output.
+    id: u32,
+}
+
+impl VideoOutput {
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn display_type(&self) -> DisplayType {
+        DisplayType::from_value(((self.id >> DOD_TYPE_SHIFT) & DOD_TYPE_MASK) as u8)
+    }
+
+    /// Whether the output has a backlight that can be controlled.
+    pub fn has_backlight(&self) -> bool {
+        self.device.has_child("_BCL") && self.device.has_child("_BCM")
+    }
+
+    /// Evaluates `_BCL`.
+    pub fn brightness_levels(&self) -> Result<BrightnessLevels, ACPI_STATUS> {
+        let elements = self.device.evaluate_package("_BCL", &[])?;
+
+        let values = elements
+            .iter()
+            .map(|element| integer(element).map(|level| level as u32))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [ac, battery, levels @ ..] = values.as_slice() else {
+            return Err(AE_TYPE);
+        };
+
+        // Firmware often lists the levels unsorted or twice
+        let mut levels = levels.to_vec();
+        levels.sort_unstable();
+        levels.dedup();
+
+        if levels.is_empty() {
+            return Err(AE_TYPE);
+        }
+
+        Ok(BrightnessLevels {
+            ac: *ac,
+            battery: *battery,
+            levels,
+        })
+    }
+
+    /// Evaluates `_BQC`, the current brightness level.
+    pub fn brightness(&self) -> Result<u32, ACPI_STATUS> {
+        Ok(self.device.evaluate_integer("_BQC", &[])? as u32)
+    }
+
+    /// Sets the brightness through `_BCM`. `level` has to be one of
+    /// [`BrightnessLevels::levels`].
+    pub fn set_brightness(&self, level: u32) -> Result<(), ACPI_STATUS> {
+        self.device
+            .evaluate("_BCM", &[Object::Integer(level as u64)])
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,453 @@
+//! Control method batteries and AC adapters.
+//!
+//! A battery device (`PNP0C0A`) reports its static information through `_BIF`, or `_BIX` on
+//! newer platforms, and its current state through `_BST`. Capacities are either in mWh or in
+//! mAh, as told by the power unit of the information package. An AC adapter (`ACPI0003`)
+//! reports whether it is online through `_PSR`. Both notify the OS when these change.
+
+use acpica_sys::*;
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    namespace::{self, integer, Handle, Object},
+    notify::{Notify, NotifySubscription, NotifyType},
+    AE_TYPE,
+};
+
+/// The `_HID` of control method batteries.
+pub const BATTERY_HID: &str = "PNP0C0A";
+
+/// The `_HID` of AC adapters.
+pub const AC_ADAPTER_HID: &str = "ACPI0003";
+
+/// Power supply notification values.
+const POWER_SUPPLY_NOTIFY_STATUS: u8 = 0x80;
+const POWER_SUPPLY_NOTIFY_INFO: u8 = 0x81;
+const POWER_SUPPLY_NOTIFY_MAINTENANCE: u8 = 0x82;
+
+/// Value of package fields whose value is unknown.
+const UNKNOWN_VALUE: u64 = 0xFFFF_FFFF;
+
+/// `_STA` bit telling whether a battery is inserted.
+const STA_BATTERY_PRESENT: u64 = 1 << 4;
+
+/// Bits of the `_BST` battery state.
+const BST_DISCHARGING: u64 = 1 << 0;
+const BST_CHARGING: u64 = 1 << 1;
+const BST_CRITICAL: u64 = 1 << 2;
+const BST_CHARGE_LIMITING: u64 = 1 << 3;
+
+/// Values of the battery swapping capability of `_BIX`.
+const BIX_NOT_SWAPPABLE: u64 = 0x00;
+const BIX_COLD_SWAPPABLE: u64 = 0x01;
+const BIX_HOT_SWAPPABLE: u64 = 0x10;
+
+/// Number of entries in a `_BIF` package and in each revision of `_BIX`.
+const BIF_ENTRIES: usize = 13;
+const BIX_REVISION_0_ENTRIES: usize = 20;
+const BIX_REVISION_1_ENTRIES: usize = 21;
+
+/// Indices into a `_BIF` package. The `_BIX` fields they share follow the revision.
+const BIF_POWER_UNIT: usize = 0;
+const BIF_DESIGN_CAPACITY: usize = 1;
+const BIF_LAST_FULL_CHARGE_CAPACITY: usize = 2;
+const BIF_TECHNOLOGY: usize = 3;
+const BIF_DESIGN_VOLTAGE: usize = 4;
+const BIF_DESIGN_CAPACITY_WARNING: usize = 5;
+const BIF_DESIGN_CAPACITY_LOW: usize = 6;
+const BIF_GRANULARITY_1: usize = 7;
+const BIF_GRANULARITY_2: usize = 8;
+const BIF_MODEL_NUMBER: usize = 9;
+const BIF_SERIAL_NUMBER: usize = 10;
+const BIF_BATTERY_TYPE: usize = 11;
+const BIF_OEM_INFORMATION: usize = 12;
+
+/// Indices into a `_BIX` package.
+const BIX_REVISION: usize = 0;
+const BIX_CYCLE_COUNT: usize = 8;
+const BIX_MEASUREMENT_ACCURACY: usize = 9;
+const BIX_MAX_SAMPLING_TIME: usize = 10;
+const BIX_MIN_SAMPLING_TIME: usize = 11;
+const BIX_MAX_AVERAGING_INTERVAL: usize = 12;
+const BIX_MIN_AVERAGING_INTERVAL: usize = 13;
+const BIX_GRANULARITY_1: usize = 14;
+const BIX_SWAPPING_CAPABILITY: usize = 20;
+
+/// The unit battery capacities and rates are reported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUnit {
+    /// mWh and mW.
+    Milliwatt,
+    /// mAh and mA.
+    Milliampere,
+}
+
+/// An amount of charge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capacity {
+    MilliwattHours(u32),
+    MilliampereHours(u32),
+}
+
+impl Capacity {
+    /// The value, without its unit.
+    pub const fn value(self) -> u32 {
+        match self {
+            Self::MilliwattHours(value) | Self::MilliampereHours(value) => value,
+        }
+    }
+}
+
+/// A rate of charge or discharge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rate {
+    Milliwatts(u32),
+    Milliamperes(u32),
+}
+
+impl Rate {
+    /// The value, without its unit.
+    pub const fn value(self) -> u32 {
+        match self {
+            Self::Milliwatts(value) | Self::Milliamperes(value) => value,
+        }
+    }
+}
+
+impl PowerUnit {
+    /// Returns the unit for the power unit field, 0 for mW and 1 for mA.
+    pub const fn from_value(value: u64) -> Option<Self> {
+        match value {
+            0 => Some(Self::Milliwatt),
+            1 => Some(Self::Milliampere),
+            _ => None,
+        }
+    }
+
+    /// Attaches the unit to a capacity value, which is `None` if unknown.
+    pub const fn capacity(self, value: u64) -> Option<Capacity> {
+        match (self, value) {
+            (_, UNKNOWN_VALUE) => None,
+            (Self::Milliwatt, value) => Some(Capacity::MilliwattHours(value as u32)),
+            (Self::Milliampere, value) => Some(Capacity::MilliampereHours(value as u32)),
+        }
+    }
+
+    /// Attaches the unit to a rate value, which is `None` if unknown.
+    pub const fn rate(self, value: u64) -> Option<Rate> {
+        match (self, value) {
+            (_, UNKNOWN_VALUE) => None,
+            (Self::Milliwatt, value) => Some(Rate::Milliwatts(value as u32)),
+            (Self::Milliampere, value) => Some(Rate::Milliamperes(value as u32)),
+        }
+    }
+}
+
+/// Whether a battery can be swapped, from `_BIX` revision 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwappingCapability {
+    NotSwappable,
+    /// Only while the system is off or asleep.
+    ColdSwappable,
+    HotSwappable,
+}
+
+/// The static information of a battery, from `_BIX` or `_BIF`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatteryInfo {
+    pub power_unit: PowerUnit,
+    pub design_capacity: Option<Capacity>,
+    pub last_full_charge_capacity: Option<Capacity>,
+    pub rechargeable: bool,
+    /// In mV.
+    pub design_voltage: Option<u32>,
+    /// The capacity at which the platform warns the user.
+    pub design_capacity_warning: Option<Capacity>,
+    /// The capacity at which the platform considers the battery critical.
+    pub design_capacity_low: Option<Capacity>,
+    /// Resolution of the remaining capacity between low and warning.
+    pub granularity_1: Option<Capacity>,
+    /// Resolution of the remaining capacity between warning and full.
+    pub granularity_2: Option<Capacity>,
+    pub model_number: String,
+    pub serial_number: String,
+    pub battery_type: String,
+    pub oem_information: String,
+    /// The fields only `_BIX` has.
+    pub extended: Option<ExtendedBatteryInfo>,
+}
+
+/// The fields of `_BIX` that `_BIF` doesn't have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtendedBatteryInfo {
+    pub revision: u64,
+    pub cycle_count: Option<u32>,
+    /// In thousandths of a percent.
+    pub measurement_accuracy: u32,
+    /// Sampling times in milliseconds.
+    pub max_sampling_time: Option<u32>,
+    pub min_sampling_time: Option<u32>,
+    /// Averaging intervals in milliseconds.
+    pub max_averaging_interval: Option<u32>,
+    pub min_averaging_interval: Option<u32>,
+    /// Only in revision 1 and later.
+    pub swapping_capability: Option<SwappingCapability>,
+}
+
+/// The current state of a battery, from `_BST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryState {
+    pub discharging: bool,
+    pub charging: bool,
+    /// The battery is at or below its low capacity.
+    pub critical: bool,
+    /// Charging is limited, e.g. to preserve battery health.
+    pub charge_limiting: bool,
+    pub present_rate: Option<Rate>,
+    pub remaining_capacity: Option<Capacity>,
+    /// In mV.
+    pub present_voltage: Option<u32>,
+}
+
+/// The meaning of a notification sent to a battery or AC adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerSupplyEvent {
+    /// `_BST` or `_PSR` changed.
+    StatusChanged,
+    /// `_BIF` or `_BIX` changed, e.g. because a battery was inserted.
+    InfoChanged,
+    /// The maintenance data of a battery changed.
+    MaintenanceDataChanged,
+    /// Any other notification.
+    Other(Notify),
+}
+
+impl PowerSupplyEvent {
+    pub const fn from_notify(notify: Notify) -> Self {
+        match notify {
+            Notify::DeviceSpecific(POWER_SUPPLY_NOTIFY_STATUS) => Self::StatusChanged,
+            Notify::DeviceSpecific(POWER_SUPPLY_NOTIFY_INFO) => Self::InfoChanged,
+            Notify::DeviceSpecific(POWER_SUPPLY_NOTIFY_MAINTENANCE) => Self::MaintenanceDataChanged,
+            notify => Self::Other(notify),
+        }
+    }
+}
+
+/// A control method battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Battery {
+    device: Handle,
+}
+
+impl Battery {
+    pub fn new(device: Handle) -> Self {
+        Self { device }
+    }
+
+    /// Finds all battery devices, whether a battery is inserted or not.
+    pub fn all() -> Result<Vec<Self>, ACPI_STATUS> {
+        Ok(namespace::find_devices(BATTERY_HID)?
+            .into_iter()
+            .map(Self::new)
+            .collect())
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Whether a battery is inserted in the slot, from `_STA`.
+    pub fn is_present(&self) -> Result<bool, ACPI_STATUS> {
+        Ok(self.device.evaluate_integer("_STA", &[])? & STA_BATTERY_PRESENT != 0)
+    }
+
+    /// Evaluates `_BIX`, or `_BIF` if the battery doesn't have it.
+    pub fn info(&self) -> Result<BatteryInfo, ACPI_STATUS> {
+        if self.device.has_child("_BIX") {
+            let elements = self.device.evaluate_package("_BIX", &[])?;
+            return bix(&elements);
+        }
+
+        let elements = self.device.evaluate_package("_BIF", &[])?;
+
+        if elements.len() < BIF_ENTRIES {
+            return Err(AE_TYPE);
+        }
+
+        common_info(&elements, None)
+    }
+
+    /// Evaluates `_BST`.
+    ///
+    /// `power_unit` is the one of [`Battery::info`], which `_BST` doesn't repeat.
+    pub fn state(&self, power_unit: PowerUnit) -> Result<BatteryState, ACPI_STATUS> {
+        let elements = self.device.evaluate_package("_BST", &[])?;
+
+        let [state, rate, capacity, voltage] = elements.as_slice() else {
+            return Err(AE_TYPE);
+        };
+        let state = integer(state)?;
+
+        Ok(BatteryState {
+            discharging: state & BST_DISCHARGING != 0,
+            charging: state & BST_CHARGING != 0,
+            critical: state & BST_CRITICAL != 0,
+            charge_limiting: state & BST_CHARGE_LIMITING != 0,
+            present_rate: power_unit.rate(integer(rate)?),
+            remaining_capacity: power_unit.capacity(integer(capacity)?),
+            present_voltage: known(integer(voltage)?),
+        })
+    }
+
+    /// Sets the remaining capacity at which the battery notifies a status change through
+    /// `_BTP`, in the power unit of the battery. `None` clears the trip point.
+    pub fn set_trip_point(&self, capacity: Option<u32>) -> Result<(), ACPI_STATUS> {
+        self.device
+            .evaluate("_BTP", &[Object::Integer(capacity.unwrap_or(0) as u64)])
+            .map(|_| ())
+    }
+
+    /// Calls `handler` with the notifications AML sends to the battery.
+    pub fn on_event<F>(&self, handler: F) -> Result<NotifySubscription, ACPI_STATUS>
+    where
+        F: Fn(Battery, PowerSupplyEvent) + Send + Sync + 'static,
+    {
+        self.device
+            .on_notify(NotifyType::Device, move |device, notify| {
+                handler(Battery::new(device), PowerSupplyEvent::from_notify(notify))
+            })
+    }
+}
+
+/// An AC adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AcAdapter {
+    device: Handle,
+}
+
+impl AcAdapter {
+    pub fn new(device: Handle) -> Self {
+        Self { device }
+    }
+
+    /// Finds all AC adapter devices.
+    pub fn all() -> Result<Vec<Self>, ACPI_STATUS> {
+        Ok(namespace::find_devices(AC_ADAPTER_HID)?
+            .into_iter()
+            .map(Self::new)
+            .collect())
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Evaluates `_PSR`, which tells whether the adapter is plugged in.
+    pub fn is_online(&self) -> Result<bool, ACPI_STATUS> {
+        Ok(self.device.evaluate_integer("_PSR", &[])? != 0)
+    }
+
+    /// Calls `handler` with the notifications AML sends to the adapter.
+    pub fn on_event<F>(&self, handler: F) -> Result<NotifySubscription, ACPI_STATUS>
+    where
+        F: Fn(AcAdapter, PowerSupplyEvent) + Send + Sync + 'static,
+    {
+        self.device
+            .on_notify(NotifyType::Device, move |device, notify| {
+                handler(
+                    AcAdapter::new(device),
+                    PowerSupplyEvent::from_notify(notify),
+                )
+            })
+    }
+}
+
+/// Decodes a `_BIX` package of any revision.
+fn bix(elements: &[Object]) -> Result<BatteryInfo, ACPI_STATUS> {
+    let revision = integer(elements.first().ok_or(AE_TYPE)?)?;
+
+    let entries = match revision {
+        0 => BIX_REVISION_0_ENTRIES,
+        _ => BIX_REVISION_1_ENTRIES,
+    };
+
+    if elements.len() < entries {
+        return Err(AE_TYPE);
+    }
+
+    let value = |index: usize| integer(&elements[index]);
+
+    let swapping_capability = match revision {
+        0 => None,
+        _ => match value(BIX_SWAPPING_CAPABILITY)? {
+            BIX_NOT_SWAPPABLE => Some(SwappingCapability::NotSwappable),
+            BIX_COLD_SWAPPABLE => Some(SwappingCapability::ColdSwappable),
+            BIX_HOT_SWAPPABLE => Some(SwappingCapability::HotSwappable),
+            _ => None,
+        },
+    };
+
+    let extended = ExtendedBatteryInfo {
+        revision,
+        cycle_count: known(value(BIX_CYCLE_COUNT)?),
+        measurement_accuracy: value(BIX_MEASUREMENT_ACCURACY)? as u32,
+        max_sampling_time: known(value(BIX_MAX_SAMPLING_TIME)?),
+        min_sampling_time: known(value(BIX_MIN_SAMPLING_TIME)?),
+        max_averaging_interval: known(value(BIX_MAX_AVERAGING_INTERVAL)?),
+        min_averaging_interval: known(value(BIX_MIN_AVERAGING_INTERVAL)?),
+        swapping_capability,
+    };
+
+    // Without the revision and the BIX only fields, the rest is laid out like _BIF
+    let mut fields = elements[BIX_REVISION + 1..BIX_CYCLE_COUNT].to_vec();
+    fields.extend_from_slice(&elements[BIX_GRANULARITY_1..BIX_SWAPPING_CAPABILITY]);
+
+    common_info(&fields, Some(extended))
+}
+
+/// Decodes the fields `_BIF` and `_BIX` share, laid out like `_BIF`.
+fn common_info(
+    elements: &[Object],
+    extended: Option<ExtendedBatteryInfo>,
+) -> Result<BatteryInfo, ACPI_STATUS> {
+    let value = |index: usize| integer(&elements[index]);
+
+    let power_unit = PowerUnit::from_value(value(BIF_POWER_UNIT)?).ok_or(AE_TYPE)?;
+
+    Ok(BatteryInfo {
+        power_unit,
+        design_capacity: power_unit.capacity(value(BIF_DESIGN_CAPACITY)?),
+        last_full_charge_capacity: power_unit.capacity(value(BIF_LAST_FULL_CHARGE_CAPACITY)?),
+        rechargeable: value(BIF_TECHNOLOGY)? == 1,
+        design_voltage: known(value(BIF_DESIGN_VOLTAGE)?),
+        design_capacity_warning: power_unit.capacity(value(BIF_DESIGN_CAPACITY_WARNING)?),
+        design_capacity_low: power_unit.capacity(value(BIF_DESIGN_CAPACITY_LOW)?),
+        granularity_1: power_unit.capacity(value(BIF_GRANULARITY_1)?),
+        granularity_2: power_unit.capacity(value(BIF_GRANULARITY_2)?),
+        model_number: string(&elements[BIF_MODEL_NUMBER]),
+        serial_number: string(&elements[BIF_SERIAL_NUMBER]),
+        battery_type: string(&elements[BIF_BATTERY_TYPE]),
+        oem_information: string(&elements[BIF_OEM_INFORMATION]),
+        extended,
+    })
+}
+
+/// Returns `None` for the value of unknown fields.
+fn known(value: u64) -> Option<u32> {
+    (value != UNKNOWN_VALUE).then_some(value as u32)
+}
+
+/// Reads a string field. Some firmware returns buffers instead, or leaves the field empty.
+fn string(object: &Object) -> String {
+    match object {
+        Object::String(string) => string.clone(),
+        Object::Buffer(bytes) => {
+            let length = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..length]).into_owned()
+        }
+        _ => String::new(),
+    }
+}
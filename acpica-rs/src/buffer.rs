@@ -0,0 +1,48 @@
+use core::ffi::c_void;
+
+use acpica_sys::*;
+
+use crate::current_os_services_implementation;
+
+/// Lets ACPICA size and allocate a returned buffer.
+const ACPI_ALLOCATE_BUFFER: ACPI_SIZE = ACPI_SIZE::MAX;
+
+/// An `ACPI_BUFFER` that ACPICA allocates on behalf of the caller.
+///
+/// The memory comes from `AcpicaOsServices::allocate` and is freed again on drop.
+pub(crate) struct AcpiBuffer(ACPI_BUFFER);
+
+impl AcpiBuffer {
+    pub(crate) fn new() -> Self {
+        Self(ACPI_BUFFER {
+            Length: ACPI_ALLOCATE_BUFFER,
+            Pointer: core::ptr::null_mut(),
+        })
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut ACPI_BUFFER {
+        &mut self.0
+    }
+
+    /// Returns the returned data, or null if ACPICA did not return anything.
+    pub(crate) fn pointer(&self) -> *mut c_void {
+        self.0.Pointer
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self.0.Pointer.is_null() {
+            true => 0,
+            false => self.0.Length as usize,
+        }
+    }
+}
+
+impl Drop for AcpiBuffer {
+    fn drop(&mut self) {
+        if !self.0.Pointer.is_null() {
+            current_os_services_implementation()
+                .unwrap()
+                .free(self.0.Pointer);
+        }
+    }
+}
@@ -0,0 +1,486 @@
+//! Collaborative Processor Performance Control (CPPC).
+//!
+//! Instead of a list of performance states, `_CPC` describes an abstract, unitless
+//! performance scale and the registers through which the OS requests a level on it and
+//! reads back what was delivered. The registers can live in system memory, in the
+//! shared memory of a PCC subspace, or be fixed hardware such as MSRs. PCC and fixed
+//! hardware registers are accessed through a [`CppcMailbox`].
+
+use acpica_sys::*;
+
+use crate::{
+    namespace::{Handle, Object},
+    pcc::{PccChannel, PccMailbox},
+    region::AddressSpace,
+    resources::generic_register,
+    tables::GenericAddress,
+    AE_BAD_PARAMETER, AE_SUPPORT, AE_TYPE,
+};
+
+/// Number of entries in a `_CPC` package of each revision, the count itself included.
+const CPC_REVISION_2_ENTRIES: u64 = 21;
+const CPC_REVISION_3_ENTRIES: u64 = 23;
+
+/// Indices into a `_CPC` package.
+const CPC_NUM_ENTRIES: usize = 0;
+const CPC_REVISION: usize = 1;
+const CPC_HIGHEST_PERFORMANCE: usize = 2;
+const CPC_NOMINAL_PERFORMANCE: usize = 3;
+const CPC_LOWEST_NONLINEAR_PERFORMANCE: usize = 4;
+const CPC_LOWEST_PERFORMANCE: usize = 5;
+const CPC_GUARANTEED_PERFORMANCE: usize = 6;
+const CPC_DESIRED_PERFORMANCE: usize = 7;
+const CPC_MINIMUM_PERFORMANCE: usize = 8;
+const CPC_MAXIMUM_PERFORMANCE: usize = 9;
+const CPC_PERFORMANCE_REDUCTION_TOLERANCE: usize = 10;
+const CPC_TIME_WINDOW: usize = 11;
+const CPC_COUNTER_WRAPAROUND_TIME: usize = 12;
+const CPC_REFERENCE_COUNTER: usize = 13;
+const CPC_DELIVERED_COUNTER: usize = 14;
+const CPC_PERFORMANCE_LIMITED: usize = 15;
+const CPC_ENABLE: usize = 16;
+const CPC_AUTONOMOUS_SELECTION: usize = 17;
+const CPC_AUTONOMOUS_ACTIVITY_WINDOW: usize = 18;
+const CPC_ENERGY_PERFORMANCE_PREFERENCE: usize = 19;
+const CPC_REFERENCE_PERFORMANCE: usize = 20;
+const CPC_LOWEST_FREQUENCY: usize = 21;
+const CPC_NOMINAL_FREQUENCY: usize = 22;
+
+/// PCC commands defined for CPPC.
+const PCC_COMMAND_READ: u32 = 0x00;
+const PCC_COMMAND_WRITE: u32 = 0x01;
+
+/// A register in the shared memory of a PCC subspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PccRegister {
+    pub subspace: u8,
+    /// Byte offset into the communication space of the subspace.
+    pub offset: u64,
+    pub bit_width: u8,
+    pub bit_offset: u8,
+}
+
+impl PccRegister {
+    /// Number of bytes covering the register, at most 8.
+    fn byte_length(&self) -> Result<usize, ACPI_STATUS> {
+        match (self.bit_offset as usize + self.bit_width as usize + 7) / 8 {
+            length @ 1..=8 => Ok(length),
+            _ => Err(AE_BAD_PARAMETER),
+        }
+    }
+
+    /// Reads the register from the communication space of `channel`, which has to be its
+    /// subspace.
+    fn read_from(&self, channel: &PccChannel) -> Result<u64, ACPI_STATUS> {
+        let length = self.byte_length()?;
+        let offset = channel.communication_space_offset() + self.offset as usize;
+        let mut bytes = [0; 8];
+
+        channel.read_shared_memory(offset, &mut bytes[..length])?;
+
+        Ok((u64::from_le_bytes(bytes) >> self.bit_offset) & self.mask())
+    }
+
+    fn mask(&self) -> u64 {
+        match self.bit_width {
+            64.. => u64::MAX,
+            width => (1 << width) - 1,
+        }
+    }
+}
+
+/// An entry of `_CPC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpcRegister {
+    /// A constant value.
+    Integer(u64),
+    SystemMemory(GenericAddress),
+    /// An architecture specific register, e.g. an MSR on x86.
+    FixedHardware(GenericAddress),
+    Pcc(PccRegister),
+}
+
+impl CpcRegister {
+    /// Decodes an entry, returning `None` for registers left out by giving a null system
+    /// memory address.
+    fn from_object(object: &Object) -> Result<Option<Self>, ACPI_STATUS> {
+        if let Some(value) = object.as_integer() {
+            return Ok(Some(Self::Integer(value)));
+        }
+
+        let register = object
+            .as_buffer()
+            .and_then(generic_register)
+            .ok_or(AE_TYPE)?;
+
+        match AddressSpace::from_id(register.space_id) {
+            Some(AddressSpace::SystemMemory) if register.address == 0 => Ok(None),
+            Some(AddressSpace::SystemMemory) => Ok(Some(Self::SystemMemory(register))),
+            Some(AddressSpace::FixedHardware) => Ok(Some(Self::FixedHardware(register))),
+            // The access size field holds the subspace ID
+            Some(AddressSpace::PlatformCommunication) => Ok(Some(Self::Pcc(PccRegister {
+                subspace: register.access_width,
+                offset: register.address,
+                bit_width: register.bit_width,
+                bit_offset: register.bit_offset,
+            }))),
+            _ => Err(AE_SUPPORT),
+        }
+    }
+}
+
+/// The decoded `_CPC` of a processor, revision 2 or 3.
+///
+/// Optional registers the platform does not implement are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cpc {
+    pub revision: u8,
+    /// Maximum performance, which may only be reachable for short periods.
+    pub highest_performance: CpcRegister,
+    /// Maximum sustained performance.
+    pub nominal_performance: CpcRegister,
+    /// Lowest performance at which power savings are still nonlinear.
+    pub lowest_nonlinear_performance: CpcRegister,
+    pub lowest_performance: CpcRegister,
+    /// Current maximum sustained performance, which may be below nominal, e.g. because of
+    /// thermal constraints.
+    pub guaranteed_performance: Option<CpcRegister>,
+    /// The performance level requested by the OS.
+    pub desired_performance: CpcRegister,
+    pub minimum_performance: Option<CpcRegister>,
+    pub maximum_performance: Option<CpcRegister>,
+    pub performance_reduction_tolerance: Option<CpcRegister>,
+    /// Time window in milliseconds over which the desired performance should be delivered
+    /// on average.
+    pub time_window: Option<CpcRegister>,
+    /// Minimum time in seconds for the feedback counters to wrap around.
+    pub counter_wraparound_time: Option<CpcRegister>,
+    /// Counts at a fixed rate, the reference performance.
+    pub reference_counter: CpcRegister,
+    /// Counts at the rate of the delivered performance.
+    pub delivered_counter: CpcRegister,
+    /// Set by the platform when the delivered performance was limited below the desired
+    /// one.
+    pub performance_limited: Option<CpcRegister>,
+    /// Enables CPPC on platforms that also support the legacy `_PSS` interface.
+    pub enable: Option<CpcRegister>,
+    /// Lets the platform select the performance level on its own.
+    pub autonomous_selection: Option<CpcRegister>,
+    pub autonomous_activity_window: Option<CpcRegister>,
+    pub energy_performance_preference: Option<CpcRegister>,
+    /// Performance level at which the reference counter counts. The nominal performance if
+    /// not present.
+    pub reference_performance: Option<CpcRegister>,
+    /// Frequency in MHz of the lowest performance, revision 3 only.
+    pub lowest_frequency: Option<CpcRegister>,
+    /// Frequency in MHz of the nominal performance, revision 3 only.
+    pub nominal_frequency: Option<CpcRegister>,
+}
+
+impl Cpc {
+    /// Evaluates `_CPC` of `processor`. Fails with `AE_SUPPORT` for other revisions than 2
+    /// and 3.
+    pub fn get(processor: Handle) -> Result<Self, ACPI_STATUS> {
+        let elements = processor.evaluate_package("_CPC", &[])?;
+
+        let value = |index: usize| {
+            elements
+                .get(index)
+                .and_then(Object::as_integer)
+                .ok_or(AE_TYPE)
+        };
+
+        let revision = value(CPC_REVISION)?;
+        let entries = match revision {
+            2 => CPC_REVISION_2_ENTRIES,
+            3 => CPC_REVISION_3_ENTRIES,
+            _ => return Err(AE_SUPPORT),
+        };
+
+        if value(CPC_NUM_ENTRIES)? != entries || elements.len() as u64 != entries {
+            return Err(AE_TYPE);
+        }
+
+        let optional = |index: usize| match elements.get(index) {
+            Some(element) => CpcRegister::from_object(element),
+            None => Ok(None),
+        };
+        let required = |index: usize| optional(index)?.ok_or(AE_TYPE);
+
+        Ok(Self {
+            revision: revision as u8,
+            highest_performance: required(CPC_HIGHEST_PERFORMANCE)?,
+            nominal_performance: required(CPC_NOMINAL_PERFORMANCE)?,
+            lowest_nonlinear_performance: required(CPC_LOWEST_NONLINEAR_PERFORMANCE)?,
+            lowest_performance: required(CPC_LOWEST_PERFORMANCE)?,
+            guaranteed_performance: optional(CPC_GUARANTEED_PERFORMANCE)?,
+            desired_performance: required(CPC_DESIRED_PERFORMANCE)?,
+            minimum_performance: optional(CPC_MINIMUM_PERFORMANCE)?,
+            maximum_performance: optional(CPC_MAXIMUM_PERFORMANCE)?,
+            performance_reduction_tolerance: optional(CPC_PERFORMANCE_REDUCTION_TOLERANCE)?,
+            time_window: optional(CPC_TIME_WINDOW)?,
+            counter_wraparound_time: optional(CPC_COUNTER_WRAPAROUND_TIME)?,
+            reference_counter: required(CPC_REFERENCE_COUNTER)?,
+            delivered_counter: required(CPC_DELIVERED_COUNTER)?,
+            performance_limited: optional(CPC_PERFORMANCE_LIMITED)?,
+            enable: optional(CPC_ENABLE)?,
+            autonomous_selection: optional(CPC_AUTONOMOUS_SELECTION)?,
+            autonomous_activity_window: optional(CPC_AUTONOMOUS_ACTIVITY_WINDOW)?,
+            energy_performance_preference: optional(CPC_ENERGY_PERFORMANCE_PREFERENCE)?,
+            reference_performance: optional(CPC_REFERENCE_PERFORMANCE)?,
+            lowest_frequency: optional(CPC_LOWEST_FREQUENCY)?,
+            nominal_frequency: optional(CPC_NOMINAL_FREQUENCY)?,
+        })
+    }
+}
+
+/// Accesses the CPPC registers that are not in system memory.
+pub trait CppcMailbox: Send + Sync {
+    /// Sends a read command to the subspace of `register` and reads it.
+    fn read_pcc(&self, register: &PccRegister) -> Result<u64, ACPI_STATUS>;
+
+    /// Reads two registers of the same subspace after a single read command, so that both
+    /// values come from the same snapshot.
+    ///
+    /// The default sends a read command for each register.
+    fn read_pcc_pair(
+        &self,
+        first: &PccRegister,
+        second: &PccRegister,
+    ) -> Result<(u64, u64), ACPI_STATUS> {
+        Ok((self.read_pcc(first)?, self.read_pcc(second)?))
+    }
+
+    /// Writes `register` and sends a write command to its subspace.
+    fn write_pcc(&self, register: &PccRegister, value: u64) -> Result<(), ACPI_STATUS>;
+
+    /// Reads a fixed hardware register. Must run on the processor the register belongs to.
+    fn read_fixed_hardware(&self, _register: &GenericAddress) -> Result<u64, ACPI_STATUS> {
+        Err(AE_SUPPORT)
+    }
+
+    /// Writes a fixed hardware register. Must run on the processor the register belongs to.
+    fn write_fixed_hardware(
+        &self,
+        _register: &GenericAddress,
+        _value: u64,
+    ) -> Result<(), ACPI_STATUS> {
+        Err(AE_SUPPORT)
+    }
+}
+
+impl<M: CppcMailbox + ?Sized> CppcMailbox for &M {
+    fn read_pcc(&self, register: &PccRegister) -> Result<u64, ACPI_STATUS> {
+        (**self).read_pcc(register)
+    }
+
+    fn read_pcc_pair(
+        &self,
+        first: &PccRegister,
+        second: &PccRegister,
+    ) -> Result<(u64, u64), ACPI_STATUS> {
+        (**self).read_pcc_pair(first, second)
+    }
+
+    fn write_pcc(&self, register: &PccRegister, value: u64) -> Result<(), ACPI_STATUS> {
+        (**self).write_pcc(register, value)
+    }
+
+    fn read_fixed_hardware(&self, register: &GenericAddress) -> Result<u64, ACPI_STATUS> {
+        (**self).read_fixed_hardware(register)
+    }
+
+    fn write_fixed_hardware(
+        &self,
+        register: &GenericAddress,
+        value: u64,
+    ) -> Result<(), ACPI_STATUS> {
+        (**self).write_fixed_hardware(register, value)
+    }
+}
+
+/// PCC registers through the subspaces of the PCCT. Fixed hardware registers are not
+/// supported.
+impl CppcMailbox for PccMailbox {
+    fn read_pcc(&self, register: &PccRegister) -> Result<u64, ACPI_STATUS> {
+        self.with_channel(register.subspace, |channel| {
+            channel.send_command(PCC_COMMAND_READ, 0)?;
+            register.read_from(channel)
+        })?
+    }
+
+    /// Fails with `AE_BAD_PARAMETER` if the registers are in different subspaces.
+    fn read_pcc_pair(
+        &self,
+        first: &PccRegister,
+        second: &PccRegister,
+    ) -> Result<(u64, u64), ACPI_STATUS> {
+        if first.subspace != second.subspace {
+            return Err(AE_BAD_PARAMETER);
+        }
+
+        self.with_channel(first.subspace, |channel| {
+            channel.send_command(PCC_COMMAND_READ, 0)?;
+            Ok((first.read_from(channel)?, second.read_from(channel)?))
+        })?
+    }
+
+    fn write_pcc(&self, register: &PccRegister, value: u64) -> Result<(), ACPI_STATUS> {
+        let length = register.byte_length()?;
+        let mask = register.mask() << register.bit_offset;
+
+        self.with_channel(register.subspace, |channel| {
+            let offset = channel.communication_space_offset() + register.offset as usize;
+            let payload_length = channel.shared_memory_len() - channel.communication_space_offset();
+            let mut bytes = [0; 8];
+
+            // Keep the bits around the register
+            channel.read_shared_memory(offset, &mut bytes[..length])?;
+            let value =
+                (u64::from_le_bytes(bytes) & !mask) | ((value << register.bit_offset) & mask);
+            channel.write_shared_memory(offset, &value.to_le_bytes()[..length])?;
+
+            channel.send_command(PCC_COMMAND_WRITE, payload_length as u32)
+        })?
+    }
+}
+
+/// A snapshot of the feedback counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedbackCounters {
+    pub reference: u64,
+    pub delivered: u64,
+}
+
+impl FeedbackCounters {
+    /// Returns the average performance delivered since `earlier`, given the reference
+    /// performance, or `None` if the reference counter did not advance.
+    pub fn delivered_performance(
+        &self,
+        earlier: &FeedbackCounters,
+        reference_performance: u64,
+    ) -> Option<u64> {
+        let reference = self.reference.wrapping_sub(earlier.reference);
+        let delivered = self.delivered.wrapping_sub(earlier.delivered);
+
+        if reference == 0 {
+            return None;
+        }
+
+        Some((reference_performance as u128 * delivered as u128 / reference as u128) as u64)
+    }
+}
+
+/// The CPPC registers of a processor.
+pub struct Cppc<M: CppcMailbox> {
+    cpc: Cpc,
+    mailbox: M,
+}
+
+impl<M: CppcMailbox> Cppc<M> {
+    /// Reads the `_CPC` of `processor`, accessing its PCC and fixed hardware registers
+    /// through `mailbox`.
+    pub fn new(processor: Handle, mailbox: M) -> Result<Self, ACPI_STATUS> {
+        Ok(Self {
+            cpc: Cpc::get(processor)?,
+            mailbox,
+        })
+    }
+
+    pub fn cpc(&self) -> &Cpc {
+        &self.cpc
+    }
+
+    pub fn highest_performance(&self) -> Result<u64, ACPI_STATUS> {
+        self.read(&self.cpc.highest_performance)
+    }
+
+    pub fn nominal_performance(&self) -> Result<u64, ACPI_STATUS> {
+        self.read(&self.cpc.nominal_performance)
+    }
+
+    pub fn lowest_nonlinear_performance(&self) -> Result<u64, ACPI_STATUS> {
+        self.read(&self.cpc.lowest_nonlinear_performance)
+    }
+
+    pub fn lowest_performance(&self) -> Result<u64, ACPI_STATUS> {
+        self.read(&self.cpc.lowest_performance)
+    }
+
+    /// Reads the guaranteed performance, which is the nominal performance if the platform
+    /// does not report it.
+    pub fn guaranteed_performance(&self) -> Result<u64, ACPI_STATUS> {
+        match &self.cpc.guaranteed_performance {
+            Some(register) => self.read(register),
+            None => self.nominal_performance(),
+        }
+    }
+
+    /// Reads the performance level the reference counter counts at.
+    pub fn reference_performance(&self) -> Result<u64, ACPI_STATUS> {
+        match &self.cpc.reference_performance {
+            Some(register) => self.read(register),
+            None => self.nominal_performance(),
+        }
+    }
+
+    pub fn desired_performance(&self) -> Result<u64, ACPI_STATUS> {
+        self.read(&self.cpc.desired_performance)
+    }
+
+    /// Requests a performance level between the lowest and the highest performance.
+    pub fn set_desired_performance(&self, performance: u64) -> Result<(), ACPI_STATUS> {
+        self.write(&self.cpc.desired_performance, performance)
+    }
+
+    /// Reads the feedback counters. Delivered performance is computed from the difference
+    /// of two snapshots with [`FeedbackCounters::delivered_performance`].
+    pub fn feedback_counters(&self) -> Result<FeedbackCounters, ACPI_STATUS> {
+        // Counters in the same subspace have to come from a single read command
+        if let (CpcRegister::Pcc(reference), CpcRegister::Pcc(delivered)) =
+            (&self.cpc.reference_counter, &self.cpc.delivered_counter)
+        {
+            if reference.subspace == delivered.subspace {
+                let (reference, delivered) = self.mailbox.read_pcc_pair(reference, delivered)?;
+                return Ok(FeedbackCounters {
+                    reference,
+                    delivered,
+                });
+            }
+        }
+
+        Ok(FeedbackCounters {
+            reference: self.read(&self.cpc.reference_counter)?,
+            delivered: self.read(&self.cpc.delivered_counter)?,
+        })
+    }
+
+    /// Writes the CPPC enable register, if the platform has one.
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), ACPI_STATUS> {
+        match &self.cpc.enable {
+            Some(register) => self.write(register, enabled as u64),
+            None => Ok(()),
+        }
+    }
+
+    fn read(&self, register: &CpcRegister) -> Result<u64, ACPI_STATUS> {
+        match register {
+            CpcRegister::Integer(value) => Ok(*value),
+            CpcRegister::SystemMemory(register) => register.read(),
+            CpcRegister::FixedHardware(register) => self.mailbox.read_fixed_hardware(register),
+            CpcRegister::Pcc(register) => self.mailbox.read_pcc(register),
+        }
+    }
+
+    /// Writes a register. Constant entries can't be written.
+    fn write(&self, register: &CpcRegister, value: u64) -> Result<(), ACPI_STATUS> {
+        match register {
+            CpcRegister::Integer(_) => Err(AE_SUPPORT),
+            CpcRegister::SystemMemory(register) => register.write(value),
+            CpcRegister::FixedHardware(register) => {
+                self.mailbox.write_fixed_hardware(register, value)
+            }
+            CpcRegister::Pcc(register) => self.mailbox.write_pcc(register, value),
+        }
+    }
+}
@@ -491,7 +491,11 @@ extern "C" fn AcpiOsNotifyCommandComplete() {
 
 #[no_mangle]
 #[linkage = "external"]
-extern "C" fn AcpiOsEnterSleep(sleep_state: u32, registeravalue: u32, registerbvalue: u32) {
+extern "C" fn AcpiOsEnterSleep(
+    sleep_state: u8,
+    registeravalue: u32,
+    registerbvalue: u32,
+) -> ACPI_STATUS {
     OS_SERVICES_IMPLEMENTATION.get().unwrap().enter_sleep(
         sleep_state,
         registeravalue,
@@ -0,0 +1,461 @@
+//! Device power management.
+//!
+//! A device changes its power state through its `_PS0` to `_PS3` methods and through the
+//! power resources listed in its `_PR0` to `_PR3` packages, which have to be on for the
+//! device to be in the matching state. Power resources are often shared, e.g. by the
+//! functions of a device, so they are reference counted: a resource is turned on with the
+//! first device that needs it and off again with the last one.
+
+use acpica_sys::*;
+use alloc::{format, vec::Vec};
+use spin::{Mutex, MutexGuard};
+
+use crate::{
+    current_os_services_implementation,
+    namespace::{Handle, Object},
+    power::DeviceState,
+    AE_BAD_PARAMETER, AE_NOT_FOUND, AE_SUPPORT, AE_TYPE,
+};
+
+/// Reference counts of the power resources that are in use.
+static POWER_RESOURCE_REFERENCES: Mutex<Vec<ResourceReferences>> = Mutex::new(Vec::new());
+
+/// How long to wait before checking again whether a power resource finished turning on or
+/// off, in milliseconds.
+const POWER_RESOURCE_WAIT: u64 = 1;
+
+/// The references held to a power resource.
+struct ResourceReferences {
+    handle: Handle,
+    count: usize,
+    /// `_ON` or `_OFF` is running. They usually `Sleep`, so the lock isn't held meanwhile.
+    transitioning: bool,
+}
+
+/// The power state bookkeeping of a device.
+struct HeldState {
+    /// The state whose power resources the device holds references to.
+    state: Option<DeviceState>,
+    /// [`DevicePower::set_state`] is running. `_PSx`, `_ON` and `_OFF` usually `Sleep`, so
+    /// the lock isn't held meanwhile.
+    transitioning: bool,
+}
+
+/// A power resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PowerResource {
+    handle: Handle,
+    /// The deepest sleep state the system can be in with the resource on.
+    pub system_level: u32,
+    /// Resources are turned on in ascending and off in descending order.
+    pub resource_order: u32,
+}
+
+impl PowerResource {
+    pub fn new(handle: Handle) -> Result<Self, ACPI_STATUS> {
+        match handle.evaluate("", &[])? {
+            Some(Object::PowerResource {
+                system_level,
+                resource_order,
+            }) => Ok(Self {
+                handle,
+                system_level,
+                resource_order,
+            }),
+            _ => Err(AE_TYPE),
+        }
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Evaluates `_STA`, which tells whether the resource is on.
+    pub fn is_on(&self) -> Result<bool, ACPI_STATUS> {
+        Ok(self.handle.evaluate_integer("_STA", &[])? & 1 != 0)
+    }
+
+    /// Number of references held by devices.
+    pub fn reference_count(&self) -> usize {
+        POWER_RESOURCE_REFERENCES
+            .lock()
+            .iter()
+            .find(|references| references.handle == self.handle)
+            .map_or(0, |references| references.count)
+    }
+
+    /// Adds a reference to the resource, turning it on with `_ON` if it is the first.
+    ///
+    /// Waits while another processor turns the resource on or off.
+    pub fn acquire(&self) -> Result<(), ACPI_STATUS> {
+        let mut references = lock_references(self.handle);
+
+        if let Some(references) = references
+            .iter_mut()
+            .find(|references| references.handle == self.handle)
+        {
+            references.count += 1;
+            return Ok(());
+        }
+
+        references.push(ResourceReferences {
+            handle: self.handle,
+            count: 1,
+            transitioning: true,
+        });
+        drop(references);
+
+        let result = self.handle.evaluate("_ON", &[]);
+        end_transition(self.handle, result.is_ok());
+
+        result.map(|_| ())
+    }
+
+    /// Drops a reference to the resource, turning it off with `_OFF` if it was the last.
+    ///
+    /// Waits while another processor turns the resource on or off.
+    pub fn release(&self) -> Result<(), ACPI_STATUS> {
+        let mut references = lock_references(self.handle);
+
+        let entry = references
+            .iter_mut()
+            .find(|references| references.handle == self.handle)
+            .ok_or(AE_BAD_PARAMETER)?;
+
+        if entry.count > 1 {
+            entry.count -= 1;
+            return Ok(());
+        }
+
+        entry.transitioning = true;
+        drop(references);
+
+        // The reference stays if the resource could not be turned off
+        let result = self.handle.evaluate("_OFF", &[]);
+        end_transition(self.handle, result.is_err());
+
+        result.map(|_| ())
+    }
+}
+
+/// Locks the reference counts once no `_ON` or `_OFF` of `handle` is running.
+fn lock_references(handle: Handle) -> MutexGuard<'static, Vec<ResourceReferences>> {
+    loop {
+        let references = POWER_RESOURCE_REFERENCES.lock();
+
+        if !references
+            .iter()
+            .any(|references| references.handle == handle && references.transitioning)
+        {
+            return references;
+        }
+
+        drop(references);
+        current_os_services_implementation()
+            .unwrap()
+            .sleep(POWER_RESOURCE_WAIT);
+    }
+}
+
+/// Marks the `_ON` or `_OFF` of `handle` as finished, keeping its references or dropping
+/// them.
+fn end_transition(handle: Handle, keep: bool) {
+    let mut references = POWER_RESOURCE_REFERENCES.lock();
+
+    if let Some(index) = references
+        .iter()
+        .position(|references| references.handle == handle)
+    {
+        match keep {
+            true => references[index].transitioning = false,
+            false => {
+                references.swap_remove(index);
+            }
+        }
+    }
+}
+
+/// Reads the power resources in a `_PRx` or `_PRW` style list of references.
+pub(crate) fn power_resources(handles: &[Handle]) -> Result<Vec<PowerResource>, ACPI_STATUS> {
+    let mut resources = handles
+        .iter()
+        .map(|&handle| PowerResource::new(handle))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    resources.sort_by_key(|resource| resource.resource_order);
+
+    Ok(resources)
+}
+
+/// Acquires `resources` in ascending resource order, releasing them again on failure.
+pub(crate) fn acquire_all(resources: &[PowerResource]) -> Result<(), ACPI_STATUS> {
+    for (i, resource) in resources.iter().enumerate() {
+        if let Err(status) = resource.acquire() {
+            let _ = release_all(&resources[..i]);
+            return Err(status);
+        }
+    }
+
+    Ok(())
+}
+
+/// Releases `resources` in descending resource order.
+pub(crate) fn release_all(resources: &[PowerResource]) -> Result<(), ACPI_STATUS> {
+    resources
+        .iter()
+        .rev()
+        .try_for_each(|resource| resource.release())
+}
+
+/// The power management objects of a device.
+pub struct DevicePower {
+    device: Handle,
+    /// Whether `_PS0` to `_PS3` exist.
+    methods: [bool; 4],
+    /// The power resources of `_PR0` to `_PR3`, sorted by resource order.
+    resources: [Vec<PowerResource>; 4],
+    state: Mutex<HeldState>,
+}
+
+impl DevicePower {
+    /// Reads the power management objects of `device`. Fails with `AE_SUPPORT` if it has
+    /// neither `_PS0` nor `_PR0`.
+    pub fn new(device: Handle) -> Result<Self, ACPI_STATUS> {
+        let methods = [0, 1, 2, 3].map(|i| device.has_child(&format!("_PS{}", i)));
+
+        let mut resources: [Vec<PowerResource>; 4] = Default::default();
+
+        for (i, list) in resources.iter_mut().enumerate() {
+            *list = match device.evaluate_package(&format!("_PR{}", i), &[]) {
+                Ok(elements) => {
+                    let handles: Vec<Handle> =
+                        elements.iter().filter_map(Object::as_reference).collect();
+                    power_resources(&handles)?
+                }
+                Err(AE_NOT_FOUND) => Vec::new(),
+                Err(status) => return Err(status),
+            };
+        }
+
+        if !methods[0] && resources[0].is_empty() {
+            return Err(AE_SUPPORT);
+        }
+
+        Ok(Self {
+            device,
+            methods,
+            resources,
+            state: Mutex::new(HeldState {
+                state: None,
+                transitioning: false,
+            }),
+        })
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// The power resources that have to be on for `state`. None have to be for D3cold.
+    pub fn resources(&self, state: DeviceState) -> &[PowerResource] {
+        match state {
+            DeviceState::D3Cold => &[],
+            state => &self.resources[state.id() as usize],
+        }
+    }
+
+    /// Whether the device can be put into `state`.
+    ///
+    /// D0 and D3hot are always supported. D1 and D2 need a `_PSx` method or `_PRx` list, and
+    /// D3cold needs power resources that can be turned off.
+    pub fn supports(&self, state: DeviceState) -> bool {
+        match state {
+            DeviceState::D0 | DeviceState::D3Hot => true,
+            DeviceState::D1 | DeviceState::D2 => {
+                let i = state.id() as usize;
+                self.methods[i] || !self.resources[i].is_empty()
+            }
+            DeviceState::D3Cold => !self.resources[3].is_empty() || !self.resources[0].is_empty(),
+        }
+    }
+
+    /// Returns the current power state of the device.
+    ///
+    /// Falls back to the last state set, or D0, if the device can't report its state.
+    pub fn state(&self) -> Result<DeviceState, ACPI_STATUS> {
+        match self.reported_state()? {
+            Some(state) => Ok(state),
+            None => Ok(self.state.lock().state.unwrap_or(DeviceState::D0)),
+        }
+    }
+
+    /// Returns the power state the device reports, or `None` if it has neither `_PSC` nor
+    /// power resources.
+    ///
+    /// Uses `_PSC` if the device has it. Otherwise the state is the highest powered one
+    /// whose power resources are all on.
+    pub fn reported_state(&self) -> Result<Option<DeviceState>, ACPI_STATUS> {
+        match self.device.evaluate_integer("_PSC", &[]) {
+            Ok(state) => {
+                let state = DeviceState::from_id(state).ok_or(AE_TYPE)?;
+
+                // _PSC can't tell D3hot from D3cold
+                if state == DeviceState::D3Hot
+                    && !self.resources[3].is_empty()
+                    && !all_on(&self.resources[3])?
+                {
+                    return Ok(Some(DeviceState::D3Cold));
+                }
+
+                return Ok(Some(state));
+            }
+            Err(AE_NOT_FOUND) => {}
+            Err(status) => return Err(status),
+        }
+
+        if self.resources.iter().all(|list| list.is_empty()) {
+            return Ok(None);
+        }
+
+        for state in [
+            DeviceState::D0,
+            DeviceState::D1,
+            DeviceState::D2,
+            DeviceState::D3Hot,
+        ] {
+            let resources = self.resources(state);
+
+            if !resources.is_empty() && all_on(resources)? {
+                return Ok(Some(state));
+            }
+        }
+
+        Ok(Some(DeviceState::D3Cold))
+    }
+
+    /// Puts the device into `state`.
+    ///
+    /// Power resources are turned on before `_PSx` runs when going to a higher powered
+    /// state, and turned off after it when going to a lower powered one.
+    ///
+    /// Waits while another processor changes the state of the device.
+    pub fn set_state(&self, state: DeviceState) -> Result<(), ACPI_STATUS> {
+        if !self.supports(state) {
+            return Err(AE_SUPPORT);
+        }
+
+        let mut held = self.begin_state_change();
+        let result = self.change_state(&mut held, state);
+        self.end_state_change(held);
+
+        result
+    }
+
+    /// Marks the device as changing its state once no other change is running, returning
+    /// the state whose references it holds.
+    fn begin_state_change(&self) -> Option<DeviceState> {
+        loop {
+            let mut held = self.state.lock();
+
+            if !held.transitioning {
+                held.transitioning = true;
+                return held.state;
+            }
+
+            drop(held);
+            current_os_services_implementation()
+                .unwrap()
+                .sleep(POWER_RESOURCE_WAIT);
+        }
+    }
+
+    /// Records the state whose references the device holds after a state change.
+    fn end_state_change(&self, state: Option<DeviceState>) {
+        let mut held = self.state.lock();
+
+        held.state = state;
+        held.transitioning = false;
+    }
+
+    /// Runs `_PSx` and switches the power resources, updating `current` to the state whose
+    /// references are held.
+    fn change_state(
+        &self,
+        current: &mut Option<DeviceState>,
+        state: DeviceState,
+    ) -> Result<(), ACPI_STATUS> {
+        let previous = *current;
+
+        // Without a known previous state, make sure the resources are on before _PSx runs
+        let powering_up = previous.map_or(true, |previous| state < previous);
+
+        if powering_up {
+            self.switch_resources(current, Some(state))?;
+        }
+
+        if let Err(status) = self.run_method(state) {
+            // Go back to the references held before if the device did not come up
+            if powering_up {
+                let _ = self.switch_resources(current, previous);
+            }
+
+            return Err(status);
+        }
+
+        if !powering_up {
+            self.switch_resources(current, Some(state))?;
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates `_PSE`, which prepares the device for enumeration on its bus or, with
+    /// `enable` false, ends that again.
+    pub fn set_enumeration_power(&self, enable: bool) -> Result<(), ACPI_STATUS> {
+        self.device
+            .evaluate("_PSE", &[Object::Integer(enable as u64)])
+            .map(|_| ())
+    }
+
+    /// Runs the `_PSx` method for `state`, if there is one.
+    fn run_method(&self, state: DeviceState) -> Result<(), ACPI_STATUS> {
+        let i = state.aml_id() as usize;
+
+        if !self.methods[i] {
+            return Ok(());
+        }
+
+        self.device.evaluate(&format!("_PS{}", i), &[])?;
+
+        Ok(())
+    }
+
+    /// Takes references to the power resources of `state`, then drops those of the state
+    /// held before, so that shared resources stay on.
+    fn switch_resources(
+        &self,
+        current: &mut Option<DeviceState>,
+        state: Option<DeviceState>,
+    ) -> Result<(), ACPI_STATUS> {
+        if let Some(state) = state {
+            acquire_all(self.resources(state))?;
+        }
+
+        if let Some(previous) = core::mem::replace(current, state) {
+            release_all(self.resources(previous))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether every resource in `resources` reports that it is on.
+fn all_on(resources: &[PowerResource]) -> Result<bool, ACPI_STATUS> {
+    for resource in resources {
+        if !resource.is_on()? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,98 @@
+//! Device specific methods.
+//!
+//! `_DSM` multiplexes vendor and class specific functions behind a UUID, a revision and a
+//! function index. Function 0 returns a bitmap of the functions the device implements for
+//! the UUID and revision, so calling anything else should be preceded by checking it.
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use crate::{
+    namespace::{Handle, Object},
+    uuid::Uuid,
+    AE_SUPPORT, AE_TYPE,
+};
+
+/// The function returning the bitmap of supported functions.
+const DSM_QUERY_FUNCTION: u64 = 0;
+
+/// The functions a device implements for a UUID and revision, from `_DSM` function 0.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DsmFunctions {
+    bitmap: Vec<u8>,
+}
+
+impl DsmFunctions {
+    /// Whether `function` is implemented. Nothing is if bit 0 is clear.
+    pub fn supports(&self, function: u64) -> bool {
+        let bit = |index: u64| {
+            self.bitmap
+                .get((index / 8) as usize)
+                .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+        };
+
+        bit(DSM_QUERY_FUNCTION) && bit(function)
+    }
+
+    /// The raw bitmap, bit `n` telling whether function `n` is implemented.
+    pub fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+}
+
+impl Handle {
+    /// Evaluates `_DSM` function 0 to find out which functions the device implements.
+    ///
+    /// Devices without `_DSM` implement none.
+    pub fn dsm_functions(&self, uuid: &Uuid, revision: u64) -> Result<DsmFunctions, ACPI_STATUS> {
+        if !self.has_child("_DSM") {
+            return Ok(DsmFunctions::default());
+        }
+
+        // A single integer or a buffer of any length holds the bitmap
+        let bitmap = match self.evaluate_dsm(uuid, revision, DSM_QUERY_FUNCTION, &[])? {
+            Some(Object::Buffer(bitmap)) => bitmap,
+            Some(Object::Integer(bitmap)) => bitmap.to_le_bytes().to_vec(),
+            _ => return Err(AE_TYPE),
+        };
+
+        Ok(DsmFunctions { bitmap })
+    }
+
+    /// Calls `function` of `_DSM` for `uuid` and `revision` with `args` packaged as its
+    /// fourth argument.
+    ///
+    /// Fails with `AE_SUPPORT` if function 0 does not report `function` as implemented.
+    /// Returns `None` if the function returns nothing.
+    pub fn dsm(
+        &self,
+        uuid: &Uuid,
+        revision: u64,
+        function: u64,
+        args: &[Object],
+    ) -> Result<Option<Object>, ACPI_STATUS> {
+        if !self.dsm_functions(uuid, revision)?.supports(function) {
+            return Err(AE_SUPPORT);
+        }
+
+        self.evaluate_dsm(uuid, revision, function, args)
+    }
+
+    fn evaluate_dsm(
+        &self,
+        uuid: &Uuid,
+        revision: u64,
+        function: u64,
+        args: &[Object],
+    ) -> Result<Option<Object>, ACPI_STATUS> {
+        self.evaluate(
+            "_DSM",
+            &[
+                Object::Buffer(uuid.as_bytes().to_vec()),
+                Object::Integer(revision),
+                Object::Integer(function),
+                Object::Package(args.to_vec()),
+            ],
+        )
+    }
+}
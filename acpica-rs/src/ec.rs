@@ -0,0 +1,534 @@
+//! Embedded controller (PNP0C09) driver.
+//!
+//! The EC is a microcontroller on most laptops that owns the battery, lid, hotkeys and fans.
+//! AML talks to it through `EmbeddedControl` operation regions, which [`EmbeddedController`]
+//! services once its address space handler is installed. The EC raises a GPE when it has an
+//! event pending; the GPE handler queries the event number and runs the matching `_Qxx`
+//! method of the EC device outside of interrupt context.
+
+use core::{
+    ffi::c_void,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use acpica_sys::*;
+use alloc::{format, string::String};
+use spin::{Mutex, MutexGuard, Once};
+
+use crate::{
+    current_os_services_implementation,
+    gpe::{Gpe, GpeDisposition, GpeHandlerRegistration},
+    namespace::{find_devices, Handle, Object},
+    region::{
+        self, AddressSpace, RegionAccess, RegionActivation, RegionHandler,
+        RegionHandlerRegistration, RegionRequest,
+    },
+    resources::Triggering,
+    tables::ecdt::Ecdt,
+    to_result, AE_BAD_PARAMETER, AE_IO_ERROR, AE_NOT_FOUND, AE_OK, AE_SUPPORT, AE_TIME,
+};
+
+/// The `_HID` of embedded controller devices.
+pub const EC_HID: &str = "PNP0C09";
+
+/// Bits of the EC status register.
+const EC_STATUS_OBF: u8 = 1 << 0;
+const EC_STATUS_IBF: u8 = 1 << 1;
+const EC_STATUS_BURST: u8 = 1 << 4;
+const EC_STATUS_SCI_EVT: u8 = 1 << 5;
+
+/// EC commands.
+const EC_COMMAND_READ: u8 = 0x80;
+const EC_COMMAND_WRITE: u8 = 0x81;
+const EC_COMMAND_BURST_ENABLE: u8 = 0x82;
+const EC_COMMAND_BURST_DISABLE: u8 = 0x83;
+const EC_COMMAND_QUERY: u8 = 0x84;
+
+/// What the EC returns after entering burst mode.
+const EC_BURST_ACK: u8 = 0x90;
+
+/// How long to wait for the EC to accept or produce a byte, in microseconds.
+const EC_TIMEOUT: u32 = 500_000;
+
+/// How long to wait for the global lock, in milliseconds.
+const EC_GLOBAL_LOCK_TIMEOUT: u16 = 1000;
+
+const OSL_GPE_HANDLER: ACPI_EXECUTE_TYPE = 2;
+
+/// An embedded controller.
+pub struct EmbeddedController {
+    /// Unset for an EC from the ECDT until its path resolves in the namespace.
+    device: Once<Handle>,
+    /// Path from the ECDT, for resolving the device once the namespace is loaded.
+    path: Option<String>,
+    command_port: u16,
+    data_port: u16,
+    gpe: Option<Gpe>,
+    /// Whether `_GLK` asks for the global lock around EC transactions, evaluated once the
+    /// device is known.
+    global_lock: Once<bool>,
+    transaction: Mutex<()>,
+    query_pending: AtomicBool,
+}
+
+impl EmbeddedController {
+    /// Uses the ECDT if the platform has one, or the first `PNP0C09` device otherwise.
+    pub fn find() -> Result<Self, ACPI_STATUS> {
+        Self::from_ecdt().or_else(|_| {
+            let device = find_devices(EC_HID)?
+                .into_iter()
+                .next()
+                .ok_or(AE_NOT_FOUND)?;
+
+            Self::from_device(device)
+        })
+    }
+
+    /// Creates the EC described by the ECDT.
+    ///
+    /// This works before the namespace is loaded, in which case the EC device is only looked
+    /// up when it is first needed.
+    pub fn from_ecdt() -> Result<Self, ACPI_STATUS> {
+        let ecdt = Ecdt::get()?;
+
+        if ecdt.control.space_id != AddressSpace::SystemIo.id()
+            || ecdt.data.space_id != AddressSpace::SystemIo.id()
+        {
+            return Err(AE_SUPPORT);
+        }
+
+        Ok(Self {
+            device: Once::new(),
+            global_lock: Once::new(),
+            path: Some(ecdt.path),
+            command_port: ecdt.control.address as u16,
+            data_port: ecdt.data.address as u16,
+            gpe: Some(Gpe {
+                device: None,
+                number: ecdt.gpe as u32,
+            }),
+            transaction: Mutex::new(()),
+            query_pending: AtomicBool::new(false),
+        })
+    }
+
+    /// Creates the EC for a `PNP0C09` device from its `_CRS` and `_GPE`.
+    pub fn from_device(device: Handle) -> Result<Self, ACPI_STATUS> {
+        // The data port comes first, then the command/status port
+        let ports = device.current_resources()?.io_ports();
+        let (data, command) = match ports.as_slice() {
+            [data, command, ..] => (data, command),
+            _ => return Err(AE_NOT_FOUND),
+        };
+
+        let gpe = match device.evaluate("_GPE", &[]) {
+            Ok(Some(Object::Integer(number))) => Some(Gpe {
+                device: None,
+                number: number as u32,
+            }),
+            Ok(Some(Object::Package(elements))) => match elements.as_slice() {
+                [Object::Reference(block), Object::Integer(number)] => Some(Gpe {
+                    device: Some(*block),
+                    number: *number as u32,
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        Ok(Self {
+            device: Once::initialized(device),
+            path: None,
+            command_port: command.base,
+            data_port: data.base,
+            gpe,
+            global_lock: Once::new(),
+            transaction: Mutex::new(()),
+            query_pending: AtomicBool::new(false),
+        })
+    }
+
+    /// The EC device, or `None` if the path from the ECDT does not resolve yet.
+    pub fn device(&self) -> Option<Handle> {
+        if let Some(device) = self.device.get() {
+            return Some(*device);
+        }
+
+        let device = Handle::from_path(self.path.as_deref()?).ok()?;
+
+        Some(*self.device.call_once(|| device))
+    }
+
+    /// Whether transactions take the global lock. Until the device is known `_GLK` can't be
+    /// evaluated, so the lock is not taken.
+    fn uses_global_lock(&self) -> bool {
+        match self.device() {
+            Some(device) => *self.global_lock.call_once(|| {
+                device
+                    .evaluate_integer("_GLK", &[])
+                    .is_ok_and(|glk| glk != 0)
+            }),
+            None => false,
+        }
+    }
+
+    pub fn gpe(&self) -> Option<Gpe> {
+        self.gpe
+    }
+
+    pub fn command_port(&self) -> u16 {
+        self.command_port
+    }
+
+    pub fn data_port(&self) -> u16 {
+        self.data_port
+    }
+
+    /// Starts a transaction, which gives exclusive access to the EC until it is dropped.
+    pub fn transaction(&self) -> Result<EcTransaction<'_>, ACPI_STATUS> {
+        // Evaluated before locking, in case `_GLK` itself accesses the EC
+        let uses_global_lock = self.uses_global_lock();
+        let guard = self.transaction.lock();
+
+        let global_lock = match uses_global_lock {
+            true => {
+                let mut handle = 0;
+                to_result(unsafe { AcpiAcquireGlobalLock(EC_GLOBAL_LOCK_TIMEOUT, &mut handle) })?;
+                Some(handle)
+            }
+            false => None,
+        };
+
+        Ok(EcTransaction {
+            ec: self,
+            _guard: guard,
+            global_lock,
+            burst: false,
+        })
+    }
+
+    /// Reads a byte from the EC address space.
+    pub fn read(&self, address: u8) -> Result<u8, ACPI_STATUS> {
+        self.transaction()?.read(address)
+    }
+
+    /// Writes a byte to the EC address space.
+    pub fn write(&self, address: u8, value: u8) -> Result<(), ACPI_STATUS> {
+        self.transaction()?.write(address, value)
+    }
+
+    /// Reads consecutive bytes in burst mode.
+    pub fn read_bytes(&self, address: u8, buffer: &mut [u8]) -> Result<(), ACPI_STATUS> {
+        check_range(address, buffer.len())?;
+
+        let mut transaction = self.transaction()?;
+        transaction.enable_burst()?;
+
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = transaction.read(address + i as u8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes consecutive bytes in burst mode.
+    pub fn write_bytes(&self, address: u8, data: &[u8]) -> Result<(), ACPI_STATUS> {
+        check_range(address, data.len())?;
+
+        let mut transaction = self.transaction()?;
+        transaction.enable_burst()?;
+
+        for (i, byte) in data.iter().enumerate() {
+            transaction.write(address + i as u8, *byte)?;
+        }
+
+        Ok(())
+    }
+
+    /// Asks the EC for its next pending event, returning `None` if there is none.
+    pub fn query(&self) -> Result<Option<u8>, ACPI_STATUS> {
+        self.transaction()?.query()
+    }
+
+    /// Reads the status register.
+    pub fn status(&self) -> Result<u8, ACPI_STATUS> {
+        read_port(self.command_port)
+    }
+
+    /// Installs this EC as the handler for `EmbeddedControl` regions below its device.
+    ///
+    /// An EC created from the ECDT before the namespace is loaded serves the whole namespace.
+    pub fn install_address_space_handler(
+        &'static self,
+    ) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+        region::install_handler(
+            self.device().unwrap_or(Handle::root()),
+            AddressSpace::EmbeddedController,
+            EcRegionHandler { ec: self },
+        )
+    }
+
+    /// Installs and enables the handler for the EC GPE, which runs `_Qxx` methods for the
+    /// events the EC reports.
+    pub fn install_gpe_handler(&'static self) -> Result<EcGpeRegistration, ACPI_STATUS> {
+        let gpe = self.gpe.ok_or(AE_NOT_FOUND)?;
+
+        let registration = gpe.install_handler(Triggering::Edge, move |_| {
+            self.handle_gpe();
+            GpeDisposition::Reenable
+        })?;
+
+        // The handler is removed again if this fails
+        gpe.enable()?;
+
+        Ok(EcGpeRegistration { registration })
+    }
+
+    /// Called in interrupt context when the EC GPE fires.
+    fn handle_gpe(&'static self) {
+        let event_pending = self
+            .status()
+            .is_ok_and(|status| status & EC_STATUS_SCI_EVT != 0);
+
+        // AML can't run in the GPE handler, so the queries are deferred
+        if event_pending && !self.query_pending.swap(true, Ordering::AcqRel) {
+            let status = current_os_services_implementation().unwrap().execute(
+                OSL_GPE_HANDLER,
+                Some(ec_query_work),
+                self as *const Self as *mut c_void,
+            );
+
+            if status != AE_OK {
+                self.query_pending.store(false, Ordering::Release);
+            }
+        }
+    }
+
+    /// Runs the `_Qxx` methods for all pending events.
+    fn process_queries(&self) {
+        let Some(device) = self.device() else {
+            return;
+        };
+
+        while let Ok(Some(query)) = self.query() {
+            // Events without a method are ignored, like on other OSes
+            let _ = device.evaluate(&format!("_Q{:02X}", query), &[]);
+        }
+    }
+
+    fn wait_for(&self, ready: impl Fn(u8) -> bool) -> Result<(), ACPI_STATUS> {
+        let os_services = current_os_services_implementation().unwrap();
+
+        for _ in 0..EC_TIMEOUT {
+            if ready(self.status()?) {
+                return Ok(());
+            }
+
+            os_services.stall(1);
+        }
+
+        Err(AE_TIME)
+    }
+
+    fn send_command(&self, command: u8) -> Result<(), ACPI_STATUS> {
+        self.wait_for(|status| status & EC_STATUS_IBF == 0)?;
+        write_port(self.command_port, command)
+    }
+
+    fn write_data(&self, value: u8) -> Result<(), ACPI_STATUS> {
+        self.wait_for(|status| status & EC_STATUS_IBF == 0)?;
+        write_port(self.data_port, value)
+    }
+
+    fn read_data(&self) -> Result<u8, ACPI_STATUS> {
+        self.wait_for(|status| status & EC_STATUS_OBF != 0)?;
+        read_port(self.data_port)
+    }
+}
+
+/// Exclusive access to an EC, see [`EmbeddedController::transaction`].
+///
+/// Burst mode is left and the global lock released when the transaction is dropped.
+pub struct EcTransaction<'a> {
+    ec: &'a EmbeddedController,
+    _guard: MutexGuard<'a, ()>,
+    global_lock: Option<u32>,
+    burst: bool,
+}
+
+impl EcTransaction<'_> {
+    pub fn read(&mut self, address: u8) -> Result<u8, ACPI_STATUS> {
+        self.ec.send_command(EC_COMMAND_READ)?;
+        self.ec.write_data(address)?;
+        self.ec.read_data()
+    }
+
+    pub fn write(&mut self, address: u8, value: u8) -> Result<(), ACPI_STATUS> {
+        self.ec.send_command(EC_COMMAND_WRITE)?;
+        self.ec.write_data(address)?;
+        self.ec.write_data(value)?;
+        self.ec.wait_for(|status| status & EC_STATUS_IBF == 0)
+    }
+
+    pub fn query(&mut self) -> Result<Option<u8>, ACPI_STATUS> {
+        self.ec.send_command(EC_COMMAND_QUERY)?;
+
+        match self.ec.read_data()? {
+            0 => Ok(None),
+            query => Ok(Some(query)),
+        }
+    }
+
+    /// Asks the EC to stay dedicated to the OS, which speeds up multi byte accesses.
+    pub fn enable_burst(&mut self) -> Result<(), ACPI_STATUS> {
+        if self.burst {
+            return Ok(());
+        }
+
+        self.ec.send_command(EC_COMMAND_BURST_ENABLE)?;
+
+        match self.ec.read_data()? {
+            EC_BURST_ACK => {
+                self.burst = true;
+                Ok(())
+            }
+            _ => Err(AE_IO_ERROR),
+        }
+    }
+
+    pub fn disable_burst(&mut self) -> Result<(), ACPI_STATUS> {
+        if !self.burst {
+            return Ok(());
+        }
+
+        self.burst = false;
+
+        // The EC may already have left burst mode on its own
+        if self.ec.status()? & EC_STATUS_BURST == 0 {
+            return Ok(());
+        }
+
+        self.ec.send_command(EC_COMMAND_BURST_DISABLE)?;
+        self.ec.wait_for(|status| status & EC_STATUS_IBF == 0)
+    }
+}
+
+impl Drop for EcTransaction<'_> {
+    fn drop(&mut self) {
+        let _ = self.disable_burst();
+
+        if let Some(handle) = self.global_lock {
+            unsafe { AcpiReleaseGlobalLock(handle) };
+        }
+    }
+}
+
+/// An installed EC GPE handler. Dropping it disables the GPE and removes the handler.
+pub struct EcGpeRegistration {
+    registration: GpeHandlerRegistration,
+}
+
+impl EcGpeRegistration {
+    pub fn gpe(&self) -> Gpe {
+        self.registration.gpe()
+    }
+}
+
+impl Drop for EcGpeRegistration {
+    fn drop(&mut self) {
+        // The handler itself is removed when the registration is dropped afterwards
+        let _ = self.gpe().disable();
+    }
+}
+
+/// Services `EmbeddedControl` regions through an [`EmbeddedController`].
+struct EcRegionHandler {
+    ec: &'static EmbeddedController,
+}
+
+impl RegionHandler for EcRegionHandler {
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        let bytes = request.bit_width() as usize / 8;
+
+        if request.bit_width() % 8 != 0 || bytes == 0 || request.address() > u8::MAX as u64 {
+            return Err(AE_BAD_PARAMETER);
+        }
+
+        let address = request.address() as u8;
+        check_range(address, bytes)?;
+
+        let mut transaction = self.ec.transaction()?;
+
+        // Burst mode is only worth it, and only needed, for multi byte fields
+        if bytes > 1 {
+            transaction.enable_burst()?;
+        }
+
+        match request.access() {
+            RegionAccess::Read => {
+                let mut value = 0;
+
+                for i in 0..bytes {
+                    value |= (transaction.read(address + i as u8)? as u64) << (i * 8);
+                }
+
+                request.set_value(value);
+            }
+            RegionAccess::Write => {
+                let value = request.value();
+
+                for i in 0..bytes {
+                    transaction.write(address + i as u8, (value >> (i * 8)) as u8)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn ec_query_work(Context: *mut c_void) {
+    let ec = &*(Context as *const EmbeddedController);
+
+    // Events raised from here on queue another run
+    ec.query_pending.store(false, Ordering::Release);
+    ec.process_queries();
+}
+
+/// Fails with `AE_BAD_PARAMETER` if `length` bytes at `address` exceed the EC address space.
+fn check_range(address: u8, length: usize) -> Result<(), ACPI_STATUS> {
+    match address as usize + length {
+        end if end <= 256 => Ok(()),
+        _ => Err(AE_BAD_PARAMETER),
+    }
+}
+
+fn read_port(port: u16) -> Result<u8, ACPI_STATUS> {
+    let mut value = 0;
+
+    to_result(current_os_services_implementation().unwrap().read_port(
+        port as ACPI_IO_ADDRESS,
+        &mut value,
+        8,
+    ))?;
+
+    Ok(value as u8)
+}
+
+fn write_port(port: u16, value: u8) -> Result<(), ACPI_STATUS> {
+    to_result(current_os_services_implementation().unwrap().write_port(
+        port as ACPI_IO_ADDRESS,
+        value as u32,
+        8,
+    ))
+}
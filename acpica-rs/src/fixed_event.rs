@@ -0,0 +1,132 @@
+//! Fixed hardware events.
+//!
+//! Fixed events have dedicated status and enable bits in the PM1 registers instead of being
+//! GPEs. The power and sleep buttons are the interesting ones for an OS: their handlers
+//! typically queue a shutdown or suspend. The RTC event is mostly used for wake.
+
+use core::ffi::c_void;
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+
+use crate::{gpe::EventStatus, to_result, AE_OK};
+
+/// A fixed hardware event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FixedEvent {
+    /// The PM timer carried into its most significant bit.
+    PmTimer,
+    /// The firmware released the global lock the OS is waiting for. ACPICA handles this one
+    /// itself.
+    GlobalLock,
+    PowerButton,
+    SleepButton,
+    /// The RTC alarm fired.
+    Rtc,
+}
+
+impl FixedEvent {
+    /// Returns the `ACPI_EVENT_*` id of the event.
+    pub const fn id(self) -> u32 {
+        match self {
+            Self::PmTimer => ACPI_EVENT_PMTIMER,
+            Self::GlobalLock => ACPI_EVENT_GLOBAL,
+            Self::PowerButton => ACPI_EVENT_POWER_BUTTON,
+            Self::SleepButton => ACPI_EVENT_SLEEP_BUTTON,
+            Self::Rtc => ACPI_EVENT_RTC,
+        }
+    }
+
+    /// Sets the enable bit of the event.
+    pub fn enable(self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiEnableEvent(self.id(), 0) })
+    }
+
+    /// Clears the enable bit of the event.
+    pub fn disable(self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiDisableEvent(self.id(), 0) })
+    }
+
+    /// Clears the status bit of the event.
+    pub fn clear(self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiClearEvent(self.id()) })
+    }
+
+    pub fn status(self) -> Result<EventStatus, ACPI_STATUS> {
+        let mut status = 0;
+        to_result(unsafe { AcpiGetEventStatus(self.id(), &mut status) })?;
+
+        Ok(EventStatus::from_raw(status))
+    }
+
+    /// Installs `handler` for the event and enables it. Only one handler can be installed
+    /// for each event.
+    ///
+    /// The handler runs in interrupt context, so work like shutting down has to be deferred.
+    pub fn install_handler<F>(self, handler: F) -> Result<FixedEventRegistration, ACPI_STATUS>
+    where
+        F: Fn(FixedEvent) + Send + Sync + 'static,
+    {
+        let context = Box::into_raw(Box::new(FixedEventContext {
+            event: self,
+            handler: Box::new(handler),
+        }));
+
+        let status = unsafe {
+            AcpiInstallFixedEventHandler(
+                self.id(),
+                Some(fixed_event_handler),
+                context as *mut c_void,
+            )
+        };
+
+        if let Err(status) = to_result(status) {
+            drop(unsafe { Box::from_raw(context) });
+            return Err(status);
+        }
+
+        Ok(FixedEventRegistration {
+            event: self,
+            context,
+        })
+    }
+}
+
+struct FixedEventContext {
+    event: FixedEvent,
+    handler: Box<dyn Fn(FixedEvent) + Send + Sync>,
+}
+
+/// An installed fixed event handler. Dropping it disables the event and removes the
+/// handler.
+pub struct FixedEventRegistration {
+    event: FixedEvent,
+    context: *mut FixedEventContext,
+}
+
+unsafe impl Send for FixedEventRegistration {}
+unsafe impl Sync for FixedEventRegistration {}
+
+impl FixedEventRegistration {
+    pub fn event(&self) -> FixedEvent {
+        self.event
+    }
+}
+
+impl Drop for FixedEventRegistration {
+    fn drop(&mut self) {
+        let status =
+            unsafe { AcpiRemoveFixedEventHandler(self.event.id(), Some(fixed_event_handler)) };
+
+        if status == AE_OK {
+            drop(unsafe { Box::from_raw(self.context) });
+        }
+    }
+}
+
+unsafe extern "C" fn fixed_event_handler(Context: *mut c_void) -> u32 {
+    let context = &*(Context as *const FixedEventContext);
+    (context.handler)(context.event);
+
+    ACPI_INTERRUPT_HANDLED
+}
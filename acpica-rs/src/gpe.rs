@@ -0,0 +1,349 @@
+//! General purpose events.
+//!
+//! GPEs are the SCI sources described by the GPE register blocks of the FADT and by GPE
+//! block devices (`ACPI0006`). ACPICA runs the `_Lxx` and `_Exx` methods of GPEs that have
+//! them; drivers like the EC take over other GPEs with [`Gpe::install_handler`]. Runtime
+//! GPEs are reference counted: every [`Gpe::enable`] needs a matching [`Gpe::disable`].
+
+use core::{ffi::c_void, mem::offset_of};
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+
+use crate::{
+    namespace::Handle,
+    region::AddressSpace,
+    resources::Triggering,
+    tables::{read, GenericAddress, Table},
+    to_result, AE_BAD_PARAMETER, AE_NOT_FOUND, AE_OK,
+};
+
+/// The `_HID` of GPE block devices.
+pub const GPE_BLOCK_HID: &str = "ACPI0006";
+
+const ACPI_GPE_LEVEL_TRIGGERED: u32 = 0x08;
+const ACPI_GPE_EDGE_TRIGGERED: u32 = 0x00;
+
+/// Bits of an `ACPI_EVENT_STATUS`.
+const ACPI_EVENT_FLAG_ENABLED: ACPI_EVENT_STATUS = 0x01;
+const ACPI_EVENT_FLAG_WAKE_ENABLED: ACPI_EVENT_STATUS = 0x02;
+const ACPI_EVENT_FLAG_STATUS_SET: ACPI_EVENT_STATUS = 0x04;
+const ACPI_EVENT_FLAG_ENABLE_SET: ACPI_EVENT_STATUS = 0x08;
+const ACPI_EVENT_FLAG_HAS_HANDLER: ACPI_EVENT_STATUS = 0x10;
+const ACPI_EVENT_FLAG_MASKED: ACPI_EVENT_STATUS = 0x20;
+
+/// A general purpose event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gpe {
+    /// The GPE block device, or `None` for the FADT GPE blocks.
+    pub device: Option<Handle>,
+    pub number: u32,
+}
+
+/// What a GPE handler wants done with the GPE once it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpeDisposition {
+    /// The event was handled; clear level triggered GPEs and enable the GPE again.
+    Reenable,
+    /// The GPE stays disabled until [`Gpe::finish`] is called, e.g. once work deferred to
+    /// thread context has run.
+    Deferred,
+}
+
+/// The state of a GPE or fixed event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventStatus {
+    /// Enabled for runtime, in software.
+    pub enabled: bool,
+    /// Enabled for wake, in software.
+    pub wake_enabled: bool,
+    /// The status bit is set in hardware: the event fired and has not been cleared.
+    pub status_set: bool,
+    /// The enable bit is set in hardware.
+    pub enable_set: bool,
+    pub has_handler: bool,
+    /// Masked with `AcpiMaskGpe`, regardless of the enable bit.
+    pub masked: bool,
+}
+
+impl EventStatus {
+    pub(crate) fn from_raw(status: ACPI_EVENT_STATUS) -> Self {
+        Self {
+            enabled: status & ACPI_EVENT_FLAG_ENABLED != 0,
+            wake_enabled: status & ACPI_EVENT_FLAG_WAKE_ENABLED != 0,
+            status_set: status & ACPI_EVENT_FLAG_STATUS_SET != 0,
+            enable_set: status & ACPI_EVENT_FLAG_ENABLE_SET != 0,
+            has_handler: status & ACPI_EVENT_FLAG_HAS_HANDLER != 0,
+            masked: status & ACPI_EVENT_FLAG_MASKED != 0,
+        }
+    }
+}
+
+impl Gpe {
+    /// A GPE of the FADT GPE blocks.
+    pub const fn new(number: u32) -> Self {
+        Self {
+            device: None,
+            number,
+        }
+    }
+
+    /// Adds a runtime reference to the GPE, enabling it in hardware on the first one.
+    pub fn enable(&self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiEnableGpe(self.raw_device(), self.number) })
+    }
+
+    /// Drops a runtime reference to the GPE, disabling it in hardware on the last one.
+    pub fn disable(&self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiDisableGpe(self.raw_device(), self.number) })
+    }
+
+    /// Sets or clears the enable bit of the GPE directly, leaving the reference count alone.
+    pub fn set(&self, enabled: bool) -> Result<(), ACPI_STATUS> {
+        let action = if enabled {
+            ACPI_GPE_ENABLE
+        } else {
+            ACPI_GPE_DISABLE
+        };
+
+        to_result(unsafe { AcpiSetGpe(self.raw_device(), self.number, action as u8) })
+    }
+
+    /// Clears the status bit of the GPE.
+    pub fn clear(&self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiClearGpe(self.raw_device(), self.number) })
+    }
+
+    /// Clears and enables the GPE again after a handler returned
+    /// [`GpeDisposition::Deferred`].
+    pub fn finish(&self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiFinishGpe(self.raw_device(), self.number) })
+    }
+
+    pub fn status(&self) -> Result<EventStatus, ACPI_STATUS> {
+        let mut status = 0;
+        to_result(unsafe { AcpiGetGpeStatus(self.raw_device(), self.number, &mut status) })?;
+
+        Ok(EventStatus::from_raw(status))
+    }
+
+    /// Marks the GPE as able to wake the system on behalf of `device`, which gets a
+    /// `DeviceWake` notification when the GPE fires without a method.
+    ///
+    /// The GPE only becomes a wake GPE once [`update_all`] runs.
+    pub fn setup_for_wake(&self, device: Handle) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiSetupGpeForWake(device.as_raw(), self.raw_device(), self.number) })
+    }
+
+    /// Sets or clears the GPE in the mask of GPEs enabled while the system sleeps. The GPE
+    /// has to be set up with [`Gpe::setup_for_wake`] first.
+    pub fn set_wake_mask(&self, enabled: bool) -> Result<(), ACPI_STATUS> {
+        let action = if enabled {
+            ACPI_GPE_ENABLE
+        } else {
+            ACPI_GPE_DISABLE
+        };
+
+        to_result(unsafe { AcpiSetGpeWakeMask(self.raw_device(), self.number, action as u8) })
+    }
+
+    /// Installs `handler` for the GPE, replacing its `_Lxx` or `_Exx` method.
+    ///
+    /// The handler runs in interrupt context with the GPE disabled, so it must not run AML.
+    /// The GPE itself still has to be enabled with [`Gpe::enable`].
+    pub fn install_handler<F>(
+        &self,
+        triggering: Triggering,
+        handler: F,
+    ) -> Result<GpeHandlerRegistration, ACPI_STATUS>
+    where
+        F: Fn(Gpe) -> GpeDisposition + Send + Sync + 'static,
+    {
+        let context = Box::into_raw(Box::new(GpeHandlerContext {
+            gpe: *self,
+            handler: Box::new(handler),
+        }));
+
+        let triggering = match triggering {
+            Triggering::Level => ACPI_GPE_LEVEL_TRIGGERED,
+            Triggering::Edge => ACPI_GPE_EDGE_TRIGGERED,
+        };
+
+        let status = unsafe {
+            AcpiInstallGpeHandler(
+                self.raw_device(),
+                self.number,
+                triggering,
+                Some(gpe_handler),
+                context as *mut c_void,
+            )
+        };
+
+        if let Err(status) = to_result(status) {
+            drop(unsafe { Box::from_raw(context) });
+            return Err(status);
+        }
+
+        Ok(GpeHandlerRegistration {
+            gpe: *self,
+            context,
+        })
+    }
+
+    fn raw_device(&self) -> ACPI_HANDLE {
+        self.device
+            .map_or(core::ptr::null_mut(), |device| device.as_raw())
+    }
+}
+
+/// Enables the GPEs that have `_Lxx` or `_Exx` methods or were set up for wake since the
+/// last call. Run this after loading tables and after [`Gpe::setup_for_wake`].
+pub fn update_all() -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiUpdateAllGpes() })
+}
+
+struct GpeHandlerContext {
+    gpe: Gpe,
+    handler: Box<dyn Fn(Gpe) -> GpeDisposition + Send + Sync>,
+}
+
+/// An installed GPE handler. Dropping it removes the handler, after which the GPE is
+/// handled by its method again, if it has one.
+pub struct GpeHandlerRegistration {
+    gpe: Gpe,
+    context: *mut GpeHandlerContext,
+}
+
+unsafe impl Send for GpeHandlerRegistration {}
+unsafe impl Sync for GpeHandlerRegistration {}
+
+impl GpeHandlerRegistration {
+    pub fn gpe(&self) -> Gpe {
+        self.gpe
+    }
+}
+
+impl Drop for GpeHandlerRegistration {
+    fn drop(&mut self) {
+        let status = unsafe {
+            AcpiRemoveGpeHandler(self.gpe.raw_device(), self.gpe.number, Some(gpe_handler))
+        };
+
+        if status == AE_OK {
+            drop(unsafe { Box::from_raw(self.context) });
+        }
+    }
+}
+
+unsafe extern "C" fn gpe_handler(
+    _GpeDevice: ACPI_HANDLE,
+    _GpeNumber: u32,
+    Context: *mut c_void,
+) -> u32 {
+    let context = &*(Context as *const GpeHandlerContext);
+
+    match (context.handler)(context.gpe) {
+        GpeDisposition::Reenable => ACPI_INTERRUPT_HANDLED | ACPI_REENABLE_GPE,
+        GpeDisposition::Deferred => ACPI_INTERRUPT_HANDLED,
+    }
+}
+
+/// An installed GPE block device. Dropping it removes the block again.
+pub struct GpeBlock {
+    device: Handle,
+    register_count: u32,
+    interrupt: u32,
+}
+
+impl GpeBlock {
+    /// Installs the GPE block described by the `_CRS` of an `ACPI0006` device.
+    ///
+    /// The first I/O or memory range holds the status registers followed by the enable
+    /// registers. Blocks without an interrupt of their own share the SCI.
+    pub fn install(device: Handle) -> Result<Self, ACPI_STATUS> {
+        let resources = device.current_resources()?;
+
+        let (space, address, length) = match resources.io_ports().first() {
+            Some(range) => (
+                AddressSpace::SystemIo,
+                range.base as u64,
+                range.length as u32,
+            ),
+            None => {
+                let range = resources
+                    .memory_ranges()
+                    .first()
+                    .copied()
+                    .ok_or(AE_NOT_FOUND)?;
+                (AddressSpace::SystemMemory, range.base as u64, range.length)
+            }
+        };
+
+        // Every register is one byte wide and comes with an enable register
+        let register_count = length / 2;
+
+        if register_count == 0 {
+            return Err(AE_BAD_PARAMETER);
+        }
+
+        let interrupt = match resources.interrupts().first() {
+            Some(&interrupt) => interrupt,
+            None => sci_interrupt()?,
+        };
+
+        let mut address: ACPI_GENERIC_ADDRESS = GenericAddress {
+            space_id: space.id(),
+            bit_width: 8,
+            bit_offset: 0,
+            access_width: 1,
+            address,
+        }
+        .into();
+
+        to_result(unsafe {
+            AcpiInstallGpeBlock(device.as_raw(), &mut address, register_count, interrupt)
+        })?;
+
+        Ok(Self {
+            device,
+            register_count,
+            interrupt,
+        })
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Number of GPEs in the block.
+    pub fn count(&self) -> u32 {
+        self.register_count * 8
+    }
+
+    pub fn interrupt(&self) -> u32 {
+        self.interrupt
+    }
+
+    /// The GPE `number` of this block, counting from zero.
+    pub fn gpe(&self, number: u32) -> Option<Gpe> {
+        (number < self.count()).then_some(Gpe {
+            device: Some(self.device),
+            number,
+        })
+    }
+}
+
+impl Drop for GpeBlock {
+    fn drop(&mut self) {
+        unsafe { AcpiRemoveGpeBlock(self.device.as_raw()) };
+    }
+}
+
+/// Looks up the SCI interrupt in the FADT.
+fn sci_interrupt() -> Result<u32, ACPI_STATUS> {
+    let fadt = Table::get(b"FACP", 1)?;
+    let sci: u16 =
+        read(fadt.bytes(), offset_of!(ACPI_TABLE_FADT, SciInterrupt)).ok_or(AE_NOT_FOUND)?;
+
+    Ok(sci as u32)
+}
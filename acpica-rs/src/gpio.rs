@@ -0,0 +1,232 @@
+//! GPIO operation regions and GPIO signalled ACPI events.
+//!
+//! AML drives GPIO pins through fields of `GeneralPurposeIo` operation regions, each of which
+//! is bound to a `GpioIo` connection. The handler installed by [`install_gpio_handler`]
+//! resolves the pins of the connection and forwards the access to a [`GpioController`].
+//!
+//! On hardware-reduced platforms, events that would otherwise be GPEs are signalled through
+//! GPIO interrupts listed in the `_AEI` object of a GPIO controller. [`GpioEventDispatcher`]
+//! collects those and runs the matching `_Exx`, `_Lxx` or `_EVT` method when the controller
+//! driver reports an interrupt.
+
+use acpica_sys::*;
+use alloc::{format, vec::Vec};
+
+use crate::{
+    namespace::{Handle, Object},
+    region::{
+        self, AddressSpace, RegionAccess, RegionActivation, RegionHandler,
+        RegionHandlerRegistration, RegionRequest,
+    },
+    resources::{raw_descriptors, GpioConnection, GpioConnectionKind, Triggering},
+    AE_BAD_PARAMETER, AE_NOT_FOUND, AE_TYPE,
+};
+
+/// Drives the pins of a GPIO controller.
+pub trait GpioController: Send + Sync + 'static {
+    /// Reads the level of `pin` of the controller named by `connection`.
+    fn read_pin(&self, connection: &GpioConnection, pin: u16) -> Result<bool, ACPI_STATUS>;
+
+    /// Drives `pin` of the controller named by `connection` high or low.
+    fn write_pin(
+        &self,
+        connection: &GpioConnection,
+        pin: u16,
+        value: bool,
+    ) -> Result<(), ACPI_STATUS>;
+}
+
+/// Installs `controller` as the handler for `GeneralPurposeIo` regions below `device`.
+///
+/// This is usually the GPIO controller device. A handler on [`Handle::root`] serves every
+/// controller, which can be told apart by [`GpioConnection::controller`].
+pub fn install_gpio_handler<C: GpioController>(
+    device: Handle,
+    controller: C,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    region::install_handler(
+        device,
+        AddressSpace::GeneralPurposeIo,
+        GpioRegionHandler { controller },
+    )
+}
+
+struct GpioRegionHandler<C> {
+    controller: C,
+}
+
+impl<C: GpioController> RegionHandler for GpioRegionHandler<C> {
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        let connection = request.connection().ok_or(AE_NOT_FOUND)?;
+        let connection = GpioConnection::parse(connection.resource).ok_or(AE_BAD_PARAMETER)?;
+
+        // The address is the index of the first pin in the connection, the width the pin count
+        let first = request.address() as usize;
+        let count = request.bit_width() as usize;
+
+        if count > 64 {
+            return Err(AE_BAD_PARAMETER);
+        }
+
+        let pins = connection
+            .pins
+            .get(first..first + count)
+            .ok_or(AE_BAD_PARAMETER)?;
+
+        match request.access() {
+            RegionAccess::Read => {
+                let mut value = 0;
+
+                for (i, pin) in pins.iter().enumerate() {
+                    if self.controller.read_pin(&connection, *pin)? {
+                        value |= 1 << i;
+                    }
+                }
+
+                request.set_value(value);
+            }
+            RegionAccess::Write => {
+                let value = request.value();
+
+                for (i, pin) in pins.iter().enumerate() {
+                    self.controller
+                        .write_pin(&connection, *pin, value & (1 << i) != 0)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A GPIO interrupt listed in `_AEI`, together with the method that handles it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpioEvent {
+    pub pin: u16,
+    /// The connection the pin was listed in, for configuring the interrupt.
+    pub connection: GpioConnection,
+    method: GpioEventMethod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GpioEventMethod {
+    /// An `_Exx` or `_Lxx` method, run without arguments.
+    Pin(Handle),
+    /// The `_EVT` method, run with the pin number.
+    Event(Handle),
+}
+
+impl GpioEvent {
+    pub fn triggering(&self) -> Triggering {
+        match self.connection.kind {
+            GpioConnectionKind::Interrupt { triggering, .. } => triggering,
+            GpioConnectionKind::Io { .. } => Triggering::Edge,
+        }
+    }
+
+    /// The method run for this event.
+    pub fn method(&self) -> Handle {
+        match self.method {
+            GpioEventMethod::Pin(method) | GpioEventMethod::Event(method) => method,
+        }
+    }
+}
+
+/// Runs the ACPI event methods of the GPIO interrupts a controller lists in `_AEI`.
+pub struct GpioEventDispatcher {
+    device: Handle,
+    events: Vec<GpioEvent>,
+}
+
+impl GpioEventDispatcher {
+    /// Collects the events of a GPIO controller device from its `_AEI`.
+    ///
+    /// Pins up to 255 are handled by `_Exx` or `_Lxx` methods of the device if they exist.
+    /// Everything else goes to `_EVT`. Pins without any method are left out.
+    pub fn new(device: Handle) -> Result<Self, ACPI_STATUS> {
+        let template = match device.evaluate("_AEI", &[])? {
+            Some(Object::Buffer(template)) => template,
+            _ => return Err(AE_TYPE),
+        };
+
+        let events = raw_descriptors(&template)
+            .filter_map(GpioConnection::parse)
+            .filter(|connection| matches!(connection.kind, GpioConnectionKind::Interrupt { .. }))
+            .flat_map(|connection| {
+                let pins = connection.pins.clone();
+
+                pins.into_iter().filter_map(move |pin| {
+                    let method = event_method(device, &connection, pin)?;
+
+                    Some(GpioEvent {
+                        pin,
+                        connection: connection.clone(),
+                        method,
+                    })
+                })
+            })
+            .collect();
+
+        Ok(Self { device, events })
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// The events the controller driver has to enable interrupts for.
+    pub fn events(&self) -> &[GpioEvent] {
+        &self.events
+    }
+
+    /// Runs the method for an interrupt on `pin`.
+    ///
+    /// This runs AML, so it has to be called from thread context rather than from the
+    /// interrupt handler of the controller. Level triggered interrupts should stay masked
+    /// until this returns.
+    pub fn dispatch(&self, pin: u16) -> Result<(), ACPI_STATUS> {
+        let event = self
+            .events
+            .iter()
+            .find(|event| event.pin == pin)
+            .ok_or(AE_NOT_FOUND)?;
+
+        match event.method {
+            GpioEventMethod::Pin(method) => method.evaluate("", &[])?,
+            GpioEventMethod::Event(method) => {
+                method.evaluate("", &[Object::Integer(pin as u64)])?
+            }
+        };
+
+        Ok(())
+    }
+}
+
+/// Finds the method handling an interrupt on `pin`.
+fn event_method(device: Handle, connection: &GpioConnection, pin: u16) -> Option<GpioEventMethod> {
+    if pin <= 0xFF {
+        let prefix = match connection.kind {
+            GpioConnectionKind::Interrupt {
+                triggering: Triggering::Level,
+                ..
+            } => 'L',
+            _ => 'E',
+        };
+
+        if let Ok(method) = device.child(&format!("_{}{:02X}", prefix, pin)) {
+            return Some(GpioEventMethod::Pin(method));
+        }
+    }
+
+    device.child("_EVT").ok().map(GpioEventMethod::Event)
+}
@@ -0,0 +1,202 @@
+//! Lid, buttons and brightness hotkeys.
+//!
+//! The power and sleep buttons are either fixed hardware events or control method devices
+//! (`PNP0C0C` and `PNP0C0E`) that notify `0x80` when pressed, depending on the FADT flags. The
+//! lid (`PNP0C0D`) notifies `0x80` when it opens or closes and reports its state through
+//! `_LID`. [`InputEvents`] combines all of them and the brightness hotkeys of the display
+//! outputs into one stream for the input layer of the OS.
+
+use core::mem::offset_of;
+
+use acpica_sys::*;
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+    backlight::{BrightnessEvent, VideoAdapter},
+    fixed_event::{FixedEvent, FixedEventRegistration},
+    namespace::{self, Handle},
+    notify::{Notify, NotifySubscription, NotifyType},
+    tables::{read, Table},
+    AE_NOT_FOUND,
+};
+
+/// The `_HID` of control method power buttons.
+pub const POWER_BUTTON_HID: &str = "PNP0C0C";
+
+/// The `_HID` of control method sleep buttons.
+pub const SLEEP_BUTTON_HID: &str = "PNP0C0E";
+
+/// The `_HID` of lids.
+pub const LID_HID: &str = "PNP0C0D";
+
+/// Notification sent by buttons when pressed and by the lid when its state changed.
+const BUTTON_NOTIFY_STATUS: u8 = 0x80;
+
+/// FADT flags telling that a button is a control method device, or missing, instead of a
+/// fixed event.
+const FADT_POWER_BUTTON: u32 = 1 << 4;
+const FADT_SLEEP_BUTTON: u32 = 1 << 5;
+
+/// A button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Button {
+    Power,
+    Sleep,
+}
+
+/// An event for the input layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEvent {
+    /// A button was pressed. `device` is `None` for the fixed hardware buttons.
+    ButtonPressed {
+        button: Button,
+        device: Option<Handle>,
+    },
+    /// The lid was opened or closed.
+    Lid { device: Handle, open: bool },
+    /// A brightness hotkey was pressed.
+    Brightness {
+        output: Handle,
+        event: BrightnessEvent,
+    },
+}
+
+/// A lid device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lid {
+    device: Handle,
+}
+
+impl Lid {
+    pub fn new(device: Handle) -> Self {
+        Self { device }
+    }
+
+    pub fn all() -> Result<Vec<Self>, ACPI_STATUS> {
+        Ok(namespace::find_devices(LID_HID)?
+            .into_iter()
+            .map(Self::new)
+            .collect())
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Evaluates `_LID`, which tells whether the lid is open.
+    pub fn is_open(&self) -> Result<bool, ACPI_STATUS> {
+        Ok(self.device.evaluate_integer("_LID", &[])? != 0)
+    }
+}
+
+/// Which buttons are fixed hardware events, from the FADT flags.
+pub fn fixed_buttons() -> Result<Vec<Button>, ACPI_STATUS> {
+    let fadt = Table::get(b"FACP", 1)?;
+    let flags: u32 = read(fadt.bytes(), offset_of!(ACPI_TABLE_FADT, Flags)).ok_or(AE_NOT_FOUND)?;
+
+    let mut buttons = Vec::new();
+
+    if flags & FADT_POWER_BUTTON == 0 {
+        buttons.push(Button::Power);
+    }
+
+    if flags & FADT_SLEEP_BUTTON == 0 {
+        buttons.push(Button::Sleep);
+    }
+
+    Ok(buttons)
+}
+
+/// The handlers feeding an input event stream. Dropping it removes them.
+pub struct InputEvents {
+    fixed_events: Vec<FixedEventRegistration>,
+    subscriptions: Vec<NotifySubscription>,
+}
+
+impl InputEvents {
+    /// Calls `handler` with the events of the fixed and control method buttons, the lids
+    /// and the brightness hotkeys of the display outputs.
+    ///
+    /// Events of the fixed buttons are reported in interrupt context, the others in thread
+    /// context. Brightness hotkeys are only notified once
+    /// [`VideoAdapter::take_control`] was called.
+    pub fn install<F>(handler: F) -> Result<Self, ACPI_STATUS>
+    where
+        F: Fn(InputEvent) + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        let mut events = Self {
+            fixed_events: Vec::new(),
+            subscriptions: Vec::new(),
+        };
+
+        for button in fixed_buttons()? {
+            let event = match button {
+                Button::Power => FixedEvent::PowerButton,
+                Button::Sleep => FixedEvent::SleepButton,
+            };
+
+            let handler = handler.clone();
+            events.fixed_events.push(event.install_handler(move |_| {
+                handler(InputEvent::ButtonPressed {
+                    button,
+                    device: None,
+                })
+            })?);
+        }
+
+        for (hid, button) in [
+            (POWER_BUTTON_HID, Button::Power),
+            (SLEEP_BUTTON_HID, Button::Sleep),
+        ] {
+            for device in namespace::find_devices(hid)? {
+                let handler = handler.clone();
+                events.subscribe(device, move |device, notify| {
+                    if notify == Notify::DeviceSpecific(BUTTON_NOTIFY_STATUS) {
+                        handler(InputEvent::ButtonPressed {
+                            button,
+                            device: Some(device),
+                        });
+                    }
+                })?;
+            }
+        }
+
+        for lid in Lid::all()? {
+            let handler = handler.clone();
+            events.subscribe(lid.device(), move |device, notify| {
+                if notify != Notify::DeviceSpecific(BUTTON_NOTIFY_STATUS) {
+                    return;
+                }
+
+                if let Ok(open) = Lid::new(device).is_open() {
+                    handler(InputEvent::Lid { device, open });
+                }
+            })?;
+        }
+
+        for adapter in VideoAdapter::all()? {
+            for output in adapter.outputs()? {
+                let handler = handler.clone();
+                events.subscribe(output.device(), move |output, notify| {
+                    if let Some(event) = BrightnessEvent::from_notify(notify) {
+                        handler(InputEvent::Brightness { output, event });
+                    }
+                })?;
+            }
+        }
+
+        Ok(events)
+    }
+
+    fn subscribe(
+        &mut self,
+        device: Handle,
+        handler: impl Fn(Handle, Notify) + Send + Sync + 'static,
+    ) -> Result<(), ACPI_STATUS> {
+        self.subscriptions
+            .push(device.on_notify(NotifyType::Device, handler)?);
+
+        Ok(())
+    }
+}
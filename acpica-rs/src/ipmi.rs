@@ -0,0 +1,116 @@
+//! IPMI operation regions.
+//!
+//! AML talks to the baseboard management controller, e.g. for power metering, through
+//! fields of `IPMI` operation regions. The upper byte of the field address is the network
+//! function and the lower byte the command. The request and response are exchanged through
+//! a buffer that starts with a status byte and a length byte, and are forwarded to an
+//! [`IpmiTransport`] provided by the OS.
+
+use acpica_sys::*;
+
+use crate::{
+    namespace::Handle,
+    region::{
+        self, AddressSpace, RegionActivation, RegionHandler, RegionHandlerRegistration,
+        RegionRequest,
+    },
+};
+
+/// Status and length bytes in front of the message data.
+const IPMI_HEADER_SIZE: usize = 2;
+const IPMI_DATA_SIZE: usize = 64;
+
+const STATUS_OFFSET: usize = 0;
+const LENGTH_OFFSET: usize = 1;
+
+/// Status codes returned to AML.
+const IPMI_STATUS_OK: u8 = 0x00;
+const IPMI_STATUS_UNKNOWN: u8 = 0x07;
+const IPMI_STATUS_TIMEOUT: u8 = 0x10;
+
+/// A request to the BMC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpmiRequest<'a> {
+    pub net_fn: u8,
+    pub command: u8,
+    pub data: &'a [u8],
+}
+
+/// Why a request could not be completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpmiError {
+    /// The BMC did not respond in time.
+    Timeout,
+    /// The request could not be sent or the response not received.
+    Failed,
+}
+
+/// Sends IPMI requests to the BMC, e.g. through a KCS, SSIF or BT interface.
+pub trait IpmiTransport: Send + Sync + 'static {
+    /// Sends `request` and waits for the response.
+    ///
+    /// The response, starting with the completion code, is written to `response`. Returns
+    /// the response length.
+    fn request(&self, request: &IpmiRequest, response: &mut [u8]) -> Result<usize, IpmiError>;
+}
+
+/// Installs `transport` as the handler for `IPMI` regions below `device`, usually the IPMI
+/// system interface device or the namespace root.
+pub fn install_ipmi_handler<T: IpmiTransport>(
+    device: Handle,
+    transport: T,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    region::install_handler(device, AddressSpace::Ipmi, IpmiRegionHandler { transport })
+}
+
+struct IpmiRegionHandler<T> {
+    transport: T,
+}
+
+impl<T: IpmiTransport> RegionHandler for IpmiRegionHandler<T> {
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        let net_fn = (request.address() >> 8) as u8;
+        let command = request.address() as u8;
+        let buffer = unsafe { request.buffer(IPMI_HEADER_SIZE + IPMI_DATA_SIZE) };
+
+        let (header, data) = buffer.split_at_mut(IPMI_HEADER_SIZE);
+        let length = (header[LENGTH_OFFSET] as usize).min(IPMI_DATA_SIZE);
+
+        // The response replaces the request in the same buffer
+        let mut message = [0; IPMI_DATA_SIZE];
+        message[..length].copy_from_slice(&data[..length]);
+
+        let ipmi_request = IpmiRequest {
+            net_fn,
+            command,
+            data: &message[..length],
+        };
+
+        // Failures are reported to AML through the status byte, not as an exception
+        match self.transport.request(&ipmi_request, data) {
+            Ok(length) => {
+                header[STATUS_OFFSET] = IPMI_STATUS_OK;
+                header[LENGTH_OFFSET] = length.min(IPMI_DATA_SIZE) as u8;
+            }
+            Err(error) => {
+                header[STATUS_OFFSET] = match error {
+                    IpmiError::Timeout => IPMI_STATUS_TIMEOUT,
+                    IpmiError::Failed => IPMI_STATUS_UNKNOWN,
+                };
+                header[LENGTH_OFFSET] = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
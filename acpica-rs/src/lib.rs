@@ -53,9 +53,37 @@
 
 extern crate alloc;
 
+pub mod backlight;
+pub mod battery;
+mod buffer;
+pub mod cppc;
 mod delegates;
+pub mod device_power;
+pub mod dsm;
+pub mod ec;
+pub mod fixed_event;
 mod format;
+pub mod gpe;
+pub mod gpio;
+pub mod input;
+pub mod ipmi;
+mod mapping;
+pub mod namespace;
+pub mod notify;
+pub mod osc;
+pub mod osi;
+pub mod pcc;
+pub mod power;
+pub mod prm;
+pub mod processor;
+pub mod region;
+pub mod resources;
+pub mod serial_bus;
 pub mod sys;
+pub mod tables;
+pub mod thermal;
+pub mod uuid;
+pub mod wake;
 
 use core::ffi::c_void;
 
@@ -69,7 +97,22 @@ pub type ACPI_MUTEX = u64;
 pub type ACPI_THREAD_ID = u64;
 pub type ACPI_CPU_FLAGS = u64;
 pub const AE_OK: ACPI_STATUS = 0;
+pub const AE_ERROR: ACPI_STATUS = 0x0001;
+pub const AE_NO_MEMORY: ACPI_STATUS = 0x0004;
+pub const AE_NOT_FOUND: ACPI_STATUS = 0x0005;
+pub const AE_NOT_EXIST: ACPI_STATUS = 0x0006;
+pub const AE_ALREADY_EXISTS: ACPI_STATUS = 0x0007;
+pub const AE_TYPE: ACPI_STATUS = 0x0008;
+pub const AE_NULL_OBJECT: ACPI_STATUS = 0x0009;
+pub const AE_SUPPORT: ACPI_STATUS = 0x000F;
+pub const AE_TIME: ACPI_STATUS = 0x0011;
+pub const AE_IO_ERROR: ACPI_STATUS = 0x001E;
 pub const AE_BAD_PARAMETER: ACPI_STATUS = 0x1001;
+pub const AE_BAD_SIGNATURE: ACPI_STATUS = 0x2001;
+pub const AE_BAD_CHECKSUM: ACPI_STATUS = 0x2003;
+pub const AE_INVALID_TABLE_LENGTH: ACPI_STATUS = 0x2005;
+pub const AE_CTRL_TERMINATE: ACPI_STATUS = 0x4003;
+pub const ACPI_ROOT_OBJECT: ACPI_HANDLE = usize::MAX as ACPI_HANDLE;
 
 static OS_SERVICES_IMPLEMENTATION: Once<Box<dyn AcpicaOsServices>> = Once::new();
 
@@ -108,6 +151,14 @@ pub fn current_os_services_implementation() -> Option<&'static Box<dyn AcpicaOsS
     OS_SERVICES_IMPLEMENTATION.get()
 }
 
+/// Converts an `ACPI_STATUS` returned by ACPICA into a `Result`.
+pub(crate) fn to_result(status: ACPI_STATUS) -> Result<(), ACPI_STATUS> {
+    match status {
+        AE_OK => Ok(()),
+        error => Err(error),
+    }
+}
+
 /// The `AcpicaOsServices` trait defines the interface required by the ACPI (Advanced Configuration and Power Interface)
 /// subsystem to interact with the underlying operating system. Implementations of this trait must provide various
 /// methods for memory management, synchronization primitives, I/O operations, and more, enabling the ACPI
@@ -432,12 +483,22 @@ pub trait AcpicaOsServices: Send + Sync {
     /// Notifies that the ACPI command is complete.
     fn notify_command_complete(&self);
 
-    /// Enters a specific sleep state.
+    /// Called by `AcpiEnterSleepState` right before it writes the sleep type and enable
+    /// bits to the PM1 control registers, e.g. to save the processor state for S3.
+    ///
+    /// * `sleep_state` - The sleep state being entered, 0 to 5.
+    /// * `register_a_value` - The value for the PM1a control register (or the sleep control
+    ///   register on hardware-reduced platforms).
+    /// * `register_b_value` - The value for the PM1b control register.
     ///
-    /// * `sleep_state` - The sleep state to enter.
-    /// * `register_a_value` - The value to write to the first register.
-    /// * `register_b_value` - The value to write to the second register.
-    fn enter_sleep(&self, sleep_state: u32, register_a_value: u32, register_b_value: u32);
+    /// Returns `AE_OK` to let ACPICA write the registers, `AE_CTRL_TERMINATE` if the OS
+    /// has entered the sleep state itself, or an error to abort the transition.
+    fn enter_sleep(
+        &self,
+        sleep_state: u8,
+        register_a_value: u32,
+        register_b_value: u32,
+    ) -> ACPI_STATUS;
 
     /// Disassembles an ACPI bytecode stream.
     ///
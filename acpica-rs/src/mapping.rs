@@ -0,0 +1,59 @@
+use core::ffi::c_void;
+
+use acpica_sys::*;
+
+use crate::{current_os_services_implementation, AE_NO_MEMORY};
+
+/// A physical memory range mapped through `AcpicaOsServices::map`.
+///
+/// The range is unmapped again when the mapping is dropped.
+pub(crate) struct PhysicalMapping {
+    virtual_address: *mut u8,
+    length: usize,
+}
+
+impl PhysicalMapping {
+    /// Maps `length` bytes starting at `physical_address`.
+    pub(crate) fn new(
+        physical_address: ACPI_PHYSICAL_ADDRESS,
+        length: usize,
+    ) -> Result<Self, ACPI_STATUS> {
+        let virtual_address = current_os_services_implementation()
+            .unwrap()
+            .map(physical_address, length as ACPI_SIZE) as *mut u8;
+
+        if virtual_address.is_null() {
+            return Err(AE_NO_MEMORY);
+        }
+
+        Ok(Self {
+            virtual_address,
+            length,
+        })
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut u8 {
+        self.virtual_address
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the mapped range as a byte slice.
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.virtual_address, self.length) }
+    }
+}
+
+impl Drop for PhysicalMapping {
+    fn drop(&mut self) {
+        current_os_services_implementation().unwrap().unmap(
+            self.virtual_address as *mut c_void,
+            self.length as ACPI_SIZE,
+        );
+    }
+}
+
+unsafe impl Send for PhysicalMapping {}
+unsafe impl Sync for PhysicalMapping {}
@@ -0,0 +1,419 @@
+//! Access to the ACPI namespace.
+
+use core::ffi::{c_char, c_void};
+
+use acpica_sys::*;
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    buffer::AcpiBuffer, resources::Resources, to_result, ACPI_ROOT_OBJECT, AE_NULL_OBJECT, AE_OK,
+    AE_TYPE,
+};
+
+/// A node in the ACPI namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle(ACPI_HANDLE);
+
+// Namespace nodes are owned by ACPICA, which does its own locking
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+impl Handle {
+    /// The root of the namespace, `\`.
+    pub const fn root() -> Self {
+        Self(ACPI_ROOT_OBJECT)
+    }
+
+    /// Wraps a handle obtained from ACPICA.
+    pub const fn from_raw(handle: ACPI_HANDLE) -> Self {
+        Self(handle)
+    }
+
+    pub const fn as_raw(&self) -> ACPI_HANDLE {
+        self.0
+    }
+
+    /// Looks up a node by its absolute path, e.g. `\_SB.PCI0.LPCB.EC0`.
+    pub fn from_path(path: &str) -> Result<Self, ACPI_STATUS> {
+        Self::root().child(path)
+    }
+
+    /// Looks up a node by a path relative to this one, e.g. `_PRW` or `LPCB.EC0`.
+    ///
+    /// Single segment names are not searched for in parent scopes.
+    pub fn child(&self, path: &str) -> Result<Self, ACPI_STATUS> {
+        let path = c_string(path);
+        let mut handle = core::ptr::null_mut();
+
+        to_result(unsafe { AcpiGetHandle(self.0, path.as_ptr(), &mut handle) })?;
+
+        Ok(Self(handle))
+    }
+
+    /// Returns whether a node exists at `path` relative to this one.
+    pub fn has_child(&self, path: &str) -> bool {
+        self.child(path).is_ok()
+    }
+
+    pub fn parent(&self) -> Result<Self, ACPI_STATUS> {
+        let mut handle = core::ptr::null_mut();
+
+        to_result(unsafe { AcpiGetParent(self.0, &mut handle) })?;
+
+        Ok(Self(handle))
+    }
+
+    /// Returns the direct children of the node of the given `ACPI_TYPE_*`.
+    pub fn children(&self, object_type: ACPI_OBJECT_TYPE) -> Result<Vec<Self>, ACPI_STATUS> {
+        walk(*self, object_type, 1)
+    }
+
+    /// Returns the `ACPI_TYPE_*` of the node.
+    pub fn object_type(&self) -> Result<ACPI_OBJECT_TYPE, ACPI_STATUS> {
+        let mut object_type = 0;
+
+        to_result(unsafe { AcpiGetType(self.0, &mut object_type) })?;
+
+        Ok(object_type)
+    }
+
+    /// Returns the absolute path of the node.
+    pub fn path(&self) -> Result<String, ACPI_STATUS> {
+        let mut buffer = AcpiBuffer::new();
+
+        to_result(unsafe { AcpiGetName(self.0, ACPI_FULL_PATHNAME, buffer.as_mut_ptr()) })?;
+
+        let bytes =
+            unsafe { core::slice::from_raw_parts(buffer.pointer() as *const u8, buffer.len()) };
+        let length = bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(bytes.len());
+
+        Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+
+    /// Evaluates the object at `path` relative to this node, or the node itself if `path`
+    /// is empty.
+    ///
+    /// Methods are run with `args`, other objects are returned as they are. Returns `None`
+    /// if a method does not return a value.
+    pub fn evaluate(&self, path: &str, args: &[Object]) -> Result<Option<Object>, ACPI_STATUS> {
+        let path = (!path.is_empty()).then(|| c_string(path));
+        let mut arguments = RawArguments::default();
+        let mut objects: Vec<ACPI_OBJECT> = args.iter().map(|arg| arguments.convert(arg)).collect();
+
+        let mut list = ACPI_OBJECT_LIST {
+            Count: objects.len() as u32,
+            Pointer: objects.as_mut_ptr(),
+        };
+
+        let mut buffer = AcpiBuffer::new();
+
+        to_result(unsafe {
+            AcpiEvaluateObject(
+                self.0,
+                path.as_ref()
+                    .map_or(core::ptr::null_mut(), |path| path.as_ptr() as ACPI_STRING),
+                match args.is_empty() {
+                    true => core::ptr::null_mut(),
+                    false => &mut list,
+                },
+                buffer.as_mut_ptr(),
+            )
+        })?;
+
+        if buffer.pointer().is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(unsafe {
+            Object::from_raw(&*(buffer.pointer() as *const ACPI_OBJECT))
+        }))
+    }
+
+    /// Evaluates an object that has to return an integer, e.g. `_STA` or `_GPE`.
+    pub fn evaluate_integer(&self, path: &str, args: &[Object]) -> Result<u64, ACPI_STATUS> {
+        match self.evaluate(path, args)? {
+            Some(Object::Integer(value)) => Ok(value),
+            Some(_) => Err(AE_TYPE),
+            None => Err(AE_NULL_OBJECT),
+        }
+    }
+
+    /// Evaluates an object that has to return a package, e.g. `_PRW` or `_BST`, returning
+    /// its elements.
+    pub fn evaluate_package(
+        &self,
+        path: &str,
+        args: &[Object],
+    ) -> Result<Vec<Object>, ACPI_STATUS> {
+        match self.evaluate(path, args)? {
+            Some(Object::Package(elements)) => Ok(elements),
+            Some(_) => Err(AE_TYPE),
+            None => Err(AE_NULL_OBJECT),
+        }
+    }
+
+    /// Returns the current resource settings (`_CRS`) of a device.
+    pub fn current_resources(&self) -> Result<Resources, ACPI_STATUS> {
+        let mut buffer = AcpiBuffer::new();
+
+        to_result(unsafe { AcpiGetCurrentResources(self.0, buffer.as_mut_ptr()) })?;
+
+        Ok(Resources::new(buffer))
+    }
+}
+
+/// Finds all present devices whose `_HID` or `_CID` matches `hid`, e.g. `PNP0C09`.
+pub fn find_devices(hid: &str) -> Result<Vec<Handle>, ACPI_STATUS> {
+    let mut hid = c_string(hid);
+    let mut devices: Vec<Handle> = Vec::new();
+
+    to_result(unsafe {
+        AcpiGetDevices(
+            hid.as_mut_ptr(),
+            Some(collect_device),
+            &mut devices as *mut Vec<Handle> as *mut c_void,
+            core::ptr::null_mut(),
+        )
+    })?;
+
+    Ok(devices)
+}
+
+/// Finds all objects of the given `ACPI_TYPE_*`, e.g. every thermal zone.
+pub fn find_objects(object_type: ACPI_OBJECT_TYPE) -> Result<Vec<Handle>, ACPI_STATUS> {
+    walk(Handle::root(), object_type, u32::MAX)
+}
+
+/// Collects the objects of `object_type` below `start`, up to `max_depth` levels deep.
+fn walk(
+    start: Handle,
+    object_type: ACPI_OBJECT_TYPE,
+    max_depth: u32,
+) -> Result<Vec<Handle>, ACPI_STATUS> {
+    let mut objects: Vec<Handle> = Vec::new();
+
+    to_result(unsafe {
+        AcpiWalkNamespace(
+            object_type,
+            start.0,
+            max_depth,
+            Some(collect_device),
+            None,
+            &mut objects as *mut Vec<Handle> as *mut c_void,
+            core::ptr::null_mut(),
+        )
+    })?;
+
+    Ok(objects)
+}
+
+unsafe extern "C" fn collect_device(
+    Object: ACPI_HANDLE,
+    _NestingLevel: u32,
+    Context: *mut c_void,
+    _ReturnValue: *mut *mut c_void,
+) -> ACPI_STATUS {
+    let devices = &mut *(Context as *mut Vec<Handle>);
+    devices.push(Handle(Object));
+
+    AE_OK
+}
+
+/// An ACPI object returned by, or passed to, an evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Object {
+    Integer(u64),
+    String(String),
+    Buffer(Vec<u8>),
+    Package(Vec<Object>),
+    /// A reference to a namespace node, e.g. a device in a `_PRW` or `_PSL` package.
+    Reference(Handle),
+    Processor {
+        id: u32,
+        block_address: u64,
+        block_length: u32,
+    },
+    PowerResource {
+        system_level: u32,
+        resource_order: u32,
+    },
+    /// A package element that was not initialized, or an unresolved name.
+    Uninitialized,
+    /// Any other object type, by its `ACPI_TYPE_*`.
+    Other(ACPI_OBJECT_TYPE),
+}
+
+impl Object {
+    /// Copies an object returned by ACPICA.
+    ///
+    /// # Safety
+    ///
+    /// The pointers inside `object` must be valid as described by its type.
+    pub unsafe fn from_raw(object: &ACPI_OBJECT) -> Self {
+        match object.Type {
+            ACPI_TYPE_INTEGER => Self::Integer(object.Integer.Value),
+            ACPI_TYPE_STRING => {
+                let bytes = raw_slice(object.String.Pointer as *const u8, object.String.Length);
+                Self::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            ACPI_TYPE_BUFFER => {
+                Self::Buffer(raw_slice(object.Buffer.Pointer, object.Buffer.Length).to_vec())
+            }
+            ACPI_TYPE_PACKAGE => Self::Package(
+                raw_slice(object.Package.Elements, object.Package.Count)
+                    .iter()
+                    .map(|element| Self::from_raw(element))
+                    .collect(),
+            ),
+            ACPI_TYPE_LOCAL_REFERENCE => Self::Reference(Handle(object.Reference.Handle)),
+            ACPI_TYPE_PROCESSOR => Self::Processor {
+                id: object.Processor.ProcId,
+                block_address: object.Processor.PblkAddress,
+                block_length: object.Processor.PblkLength,
+            },
+            ACPI_TYPE_POWER => Self::PowerResource {
+                system_level: object.PowerResource.SystemLevel,
+                resource_order: object.PowerResource.ResourceOrder,
+            },
+            ACPI_TYPE_ANY => Self::Uninitialized,
+            other => Self::Other(other),
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<u64> {
+        match self {
+            Self::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_buffer(&self) -> Option<&[u8]> {
+        match self {
+            Self::Buffer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_package(&self) -> Option<&[Object]> {
+        match self {
+            Self::Package(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<Handle> {
+        match self {
+            Self::Reference(handle) => Some(*handle),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps the element arrays of package arguments alive for the duration of an evaluation.
+#[derive(Default)]
+struct RawArguments {
+    packages: Vec<Vec<ACPI_OBJECT>>,
+}
+
+impl RawArguments {
+    /// Converts an argument to an `ACPI_OBJECT` pointing into `object`.
+    ///
+    /// ACPICA copies arguments into its own objects, so nothing is written through the
+    /// pointers.
+    fn convert(&mut self, object: &Object) -> ACPI_OBJECT {
+        let mut elements = match object {
+            Object::Package(elements) => elements
+                .iter()
+                .map(|element| self.convert(element))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        unsafe {
+            let mut raw: ACPI_OBJECT = core::mem::zeroed();
+
+            match object {
+                Object::Integer(value) => {
+                    raw.Integer.Type = ACPI_TYPE_INTEGER;
+                    raw.Integer.Value = *value;
+                }
+                Object::String(value) => {
+                    raw.String.Type = ACPI_TYPE_STRING;
+                    raw.String.Length = value.len() as u32;
+                    raw.String.Pointer = value.as_ptr() as *mut c_char;
+                }
+                Object::Buffer(value) => {
+                    raw.Buffer.Type = ACPI_TYPE_BUFFER;
+                    raw.Buffer.Length = value.len() as u32;
+                    raw.Buffer.Pointer = value.as_ptr() as *mut u8;
+                }
+                Object::Package(_) => {
+                    raw.Package.Type = ACPI_TYPE_PACKAGE;
+                    raw.Package.Count = elements.len() as u32;
+                    raw.Package.Elements = elements.as_mut_ptr();
+                    // Moving the vector does not move its elements
+                    self.packages.push(elements);
+                }
+                Object::Reference(handle) => {
+                    raw.Reference.Type = ACPI_TYPE_LOCAL_REFERENCE;
+                    raw.Reference.Handle = handle.0;
+                }
+                Object::Processor {
+                    id,
+                    block_address,
+                    block_length,
+                } => {
+                    raw.Processor.Type = ACPI_TYPE_PROCESSOR;
+                    raw.Processor.ProcId = *id;
+                    raw.Processor.PblkAddress = *block_address;
+                    raw.Processor.PblkLength = *block_length;
+                }
+                Object::PowerResource {
+                    system_level,
+                    resource_order,
+                } => {
+                    raw.PowerResource.Type = ACPI_TYPE_POWER;
+                    raw.PowerResource.SystemLevel = *system_level;
+                    raw.PowerResource.ResourceOrder = *resource_order;
+                }
+                Object::Uninitialized => raw.Type = ACPI_TYPE_ANY,
+                Object::Other(object_type) => raw.Type = *object_type,
+            }
+
+            raw
+        }
+    }
+}
+
+/// Builds a slice from a pointer and length returned by ACPICA, which may be null if empty.
+unsafe fn raw_slice<'a, T>(pointer: *const T, length: u32) -> &'a [T] {
+    match pointer.is_null() {
+        true => &[],
+        false => core::slice::from_raw_parts(pointer, length as usize),
+    }
+}
+
+/// Returns the value of an integer, e.g. a package element, failing with `AE_TYPE` for other
+/// objects.
+pub(crate) fn integer(object: &Object) -> Result<u64, ACPI_STATUS> {
+    object.as_integer().ok_or(AE_TYPE)
+}
+
+/// Copies `value` into a NUL terminated buffer.
+pub(crate) fn c_string(value: &str) -> Vec<c_char> {
+    value
+        .bytes()
+        .chain(core::iter::once(0))
+        .map(|byte| byte as c_char)
+        .collect()
+}
@@ -0,0 +1,226 @@
+//! Device notifications.
+//!
+//! AML tells the OS about changes to a device with `Notify(Device, Value)`, e.g. when a
+//! device was plugged in or the battery status changed. Values below `0x80` have the same
+//! meaning for every device; the others are defined by the device type, e.g. `0x80` is a
+//! status change for batteries and a temperature change for thermal zones.
+
+use core::ffi::c_void;
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+
+use crate::{namespace::Handle, to_result, AE_OK};
+
+const ACPI_NOTIFY_BUS_CHECK: u8 = 0x00;
+const ACPI_NOTIFY_DEVICE_CHECK: u8 = 0x01;
+const ACPI_NOTIFY_DEVICE_WAKE: u8 = 0x02;
+const ACPI_NOTIFY_EJECT_REQUEST: u8 = 0x03;
+const ACPI_NOTIFY_DEVICE_CHECK_LIGHT: u8 = 0x04;
+const ACPI_NOTIFY_FREQUENCY_MISMATCH: u8 = 0x05;
+const ACPI_NOTIFY_BUS_MODE_MISMATCH: u8 = 0x06;
+const ACPI_NOTIFY_POWER_FAULT: u8 = 0x07;
+const ACPI_NOTIFY_CAPABILITIES_CHECK: u8 = 0x08;
+const ACPI_NOTIFY_DEVICE_PLD_CHECK: u8 = 0x09;
+const ACPI_NOTIFY_LOCALITY_UPDATE: u8 = 0x0B;
+const ACPI_NOTIFY_SHUTDOWN_REQUEST: u8 = 0x0C;
+const ACPI_NOTIFY_AFFINITY_UPDATE: u8 = 0x0D;
+const ACPI_NOTIFY_MEMORY_UPDATE: u8 = 0x0E;
+const ACPI_NOTIFY_DISCONNECT_RECOVER: u8 = 0x0F;
+
+/// First value with a device specific meaning.
+const DEVICE_SPECIFIC_NOTIFY: u8 = 0x80;
+
+/// A notification value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Notify {
+    /// Re-enumerate the devices below the device, e.g. after a dock.
+    BusCheck,
+    /// The device was inserted or removed.
+    DeviceCheck,
+    /// The device woke the system.
+    DeviceWake,
+    /// The user asked to eject the device.
+    EjectRequest,
+    /// Like [`Notify::DeviceCheck`], but the device below it need not be re-enumerated.
+    DeviceCheckLight,
+    /// The device cannot work at the frequency of its bus.
+    FrequencyMismatch,
+    /// The device cannot work in the current mode of its bus.
+    BusModeMismatch,
+    /// The device could not be powered.
+    PowerFault,
+    /// The `_PDC` or `_OSC` capabilities of a processor or device have to be evaluated again.
+    CapabilitiesCheck,
+    /// `_PLD` changed.
+    PldCheck,
+    /// The system locality information, `_SLI`, changed.
+    LocalityUpdate,
+    /// The platform asks the OS to shut down, e.g. because of a thermal event.
+    ShutdownRequest,
+    /// The proximity or affinity of the device changed.
+    AffinityUpdate,
+    /// Memory bandwidth or latency changed.
+    MemoryUpdate,
+    /// The device was disconnected for error recovery.
+    DisconnectRecover,
+    /// A reserved value below `0x80`.
+    Reserved(u8),
+    /// A value of `0x80` or above. What it means depends on the type of the device, e.g.
+    /// `0x80` reports a new temperature for thermal zones and `0x81` changed trip points.
+    ///
+    /// There is no device independent thermal trip value, so crossed trip points arrive here
+    /// and are decoded by [`ThermalEvent`](crate::thermal::ThermalEvent).
+    DeviceSpecific(u8),
+}
+
+impl Notify {
+    pub const fn from_value(value: u8) -> Self {
+        match value {
+            ACPI_NOTIFY_BUS_CHECK => Self::BusCheck,
+            ACPI_NOTIFY_DEVICE_CHECK => Self::DeviceCheck,
+            ACPI_NOTIFY_DEVICE_WAKE => Self::DeviceWake,
+            ACPI_NOTIFY_EJECT_REQUEST => Self::EjectRequest,
+            ACPI_NOTIFY_DEVICE_CHECK_LIGHT => Self::DeviceCheckLight,
+            ACPI_NOTIFY_FREQUENCY_MISMATCH => Self::FrequencyMismatch,
+            ACPI_NOTIFY_BUS_MODE_MISMATCH => Self::BusModeMismatch,
+            ACPI_NOTIFY_POWER_FAULT => Self::PowerFault,
+            ACPI_NOTIFY_CAPABILITIES_CHECK => Self::CapabilitiesCheck,
+            ACPI_NOTIFY_DEVICE_PLD_CHECK => Self::PldCheck,
+            ACPI_NOTIFY_LOCALITY_UPDATE => Self::LocalityUpdate,
+            ACPI_NOTIFY_SHUTDOWN_REQUEST => Self::ShutdownRequest,
+            ACPI_NOTIFY_AFFINITY_UPDATE => Self::AffinityUpdate,
+            ACPI_NOTIFY_MEMORY_UPDATE => Self::MemoryUpdate,
+            ACPI_NOTIFY_DISCONNECT_RECOVER => Self::DisconnectRecover,
+            value if value < DEVICE_SPECIFIC_NOTIFY => Self::Reserved(value),
+            value => Self::DeviceSpecific(value),
+        }
+    }
+
+    pub const fn value(self) -> u8 {
+        match self {
+            Self::BusCheck => ACPI_NOTIFY_BUS_CHECK,
+            Self::DeviceCheck => ACPI_NOTIFY_DEVICE_CHECK,
+            Self::DeviceWake => ACPI_NOTIFY_DEVICE_WAKE,
+            Self::EjectRequest => ACPI_NOTIFY_EJECT_REQUEST,
+            Self::DeviceCheckLight => ACPI_NOTIFY_DEVICE_CHECK_LIGHT,
+            Self::FrequencyMismatch => ACPI_NOTIFY_FREQUENCY_MISMATCH,
+            Self::BusModeMismatch => ACPI_NOTIFY_BUS_MODE_MISMATCH,
+            Self::PowerFault => ACPI_NOTIFY_POWER_FAULT,
+            Self::CapabilitiesCheck => ACPI_NOTIFY_CAPABILITIES_CHECK,
+            Self::PldCheck => ACPI_NOTIFY_DEVICE_PLD_CHECK,
+            Self::LocalityUpdate => ACPI_NOTIFY_LOCALITY_UPDATE,
+            Self::ShutdownRequest => ACPI_NOTIFY_SHUTDOWN_REQUEST,
+            Self::AffinityUpdate => ACPI_NOTIFY_AFFINITY_UPDATE,
+            Self::MemoryUpdate => ACPI_NOTIFY_MEMORY_UPDATE,
+            Self::DisconnectRecover => ACPI_NOTIFY_DISCONNECT_RECOVER,
+            Self::Reserved(value) | Self::DeviceSpecific(value) => value,
+        }
+    }
+}
+
+/// Which notifications a handler receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotifyType {
+    /// Values below `0x80`.
+    System,
+    /// Values of `0x80` and above.
+    Device,
+    All,
+}
+
+impl NotifyType {
+    const fn id(self) -> u32 {
+        match self {
+            Self::System => ACPI_SYSTEM_NOTIFY,
+            Self::Device => ACPI_DEVICE_NOTIFY,
+            Self::All => ACPI_ALL_NOTIFY,
+        }
+    }
+}
+
+impl Handle {
+    /// Calls `handler` with the notifications AML sends to this device, thermal zone or
+    /// processor. A handler on [`Handle::root`] receives the notifications for every object.
+    ///
+    /// Only one handler can be installed per object and notification type. Handlers run in
+    /// thread context, so they may evaluate methods.
+    pub fn on_notify<F>(
+        &self,
+        handler_type: NotifyType,
+        handler: F,
+    ) -> Result<NotifySubscription, ACPI_STATUS>
+    where
+        F: Fn(Handle, Notify) + Send + Sync + 'static,
+    {
+        let context = Box::into_raw(Box::new(NotifyContext {
+            handler: Box::new(handler),
+        }));
+
+        let status = unsafe {
+            AcpiInstallNotifyHandler(
+                self.as_raw(),
+                handler_type.id(),
+                Some(notify_handler),
+                context as *mut c_void,
+            )
+        };
+
+        if let Err(status) = to_result(status) {
+            drop(unsafe { Box::from_raw(context) });
+            return Err(status);
+        }
+
+        Ok(NotifySubscription {
+            device: *self,
+            handler_type,
+            context,
+        })
+    }
+}
+
+struct NotifyContext {
+    handler: Box<dyn Fn(Handle, Notify) + Send + Sync>,
+}
+
+/// An installed notify handler. Dropping it removes the handler.
+pub struct NotifySubscription {
+    device: Handle,
+    handler_type: NotifyType,
+    context: *mut NotifyContext,
+}
+
+unsafe impl Send for NotifySubscription {}
+unsafe impl Sync for NotifySubscription {}
+
+impl NotifySubscription {
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    pub fn handler_type(&self) -> NotifyType {
+        self.handler_type
+    }
+}
+
+impl Drop for NotifySubscription {
+    fn drop(&mut self) {
+        // ACPICA waits for running handlers before this returns
+        let status = unsafe {
+            AcpiRemoveNotifyHandler(
+                self.device.as_raw(),
+                self.handler_type.id(),
+                Some(notify_handler),
+            )
+        };
+
+        if status == AE_OK {
+            drop(unsafe { Box::from_raw(self.context) });
+        }
+    }
+}
+
+unsafe extern "C" fn notify_handler(Device: ACPI_HANDLE, Value: u32, Context: *mut c_void) {
+    let context = &*(Context as *const NotifyContext);
+    (context.handler)(Handle::from_raw(Device), Notify::from_value(Value as u8));
+}
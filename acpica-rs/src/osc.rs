@@ -0,0 +1,297 @@
+//! Operating system capabilities.
+//!
+//! Through `_OSC` the OS tells the platform which features it supports and asks for control
+//! of others, e.g. native PCIe hotplug or AER. The platform answers with the subset it
+//! grants. The capabilities buffer starts with a status DWORD; the OS first queries with the
+//! query flag set, then commits the granted capabilities so the firmware hands them over.
+
+use core::ops::BitOr;
+
+use acpica_sys::*;
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    namespace::{Handle, Object},
+    uuid::Uuid,
+    AE_TYPE,
+};
+
+/// UUID of the platform-wide `_OSC` below `\_SB`.
+pub const PLATFORM_UUID: Uuid = Uuid::parse("0811b06e-4a27-44f9-8d60-3cbbc22e7b48").unwrap();
+
+/// UUID of the `_OSC` of PCI host bridges.
+pub const PCI_HOST_BRIDGE_UUID: Uuid = Uuid::parse("33db4d5b-1ff7-401c-9657-7441c03dd766").unwrap();
+
+/// Bits of the status DWORD.
+const OSC_QUERY_ENABLE: u32 = 1 << 0;
+const OSC_REQUEST_ERROR: u32 = 1 << 1;
+const OSC_INVALID_UUID_ERROR: u32 = 1 << 2;
+const OSC_INVALID_REVISION_ERROR: u32 = 1 << 3;
+const OSC_CAPABILITIES_MASK_ERROR: u32 = 1 << 4;
+
+/// Revision of the platform-wide and PCI host bridge capabilities buffers.
+const OSC_REVISION: u64 = 1;
+
+/// Why `_OSC` failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OscError {
+    /// Evaluating `_OSC` failed, e.g. with `AE_NOT_FOUND` if the device has none.
+    Status(ACPI_STATUS),
+    UnrecognizedUuid,
+    UnrecognizedRevision,
+    /// The platform did not grant everything that was committed. Holds the capabilities
+    /// it returned.
+    CapabilitiesMasked(Vec<u32>),
+    /// The platform reported a failure without giving a reason.
+    Failure,
+}
+
+/// The `_OSC` of a device for one UUID and revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Osc {
+    device: Handle,
+    uuid: Uuid,
+    revision: u64,
+}
+
+impl Osc {
+    pub fn new(device: Handle, uuid: Uuid, revision: u64) -> Self {
+        Self {
+            device,
+            uuid,
+            revision,
+        }
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    /// Asks which of `capabilities` the platform would grant, without changing anything.
+    ///
+    /// `capabilities` are the DWORDs following the status DWORD, as is the result.
+    pub fn query(&self, capabilities: &[u32]) -> Result<Vec<u32>, OscError> {
+        self.evaluate(true, capabilities)
+    }
+
+    /// Requests `capabilities`, which the platform then hands over.
+    pub fn commit(&self, capabilities: &[u32]) -> Result<Vec<u32>, OscError> {
+        self.evaluate(false, capabilities)
+    }
+
+    /// Queries `capabilities`, then commits the ones the platform grants.
+    ///
+    /// `granted` combines the requested DWORDs with the ones returned by the query, e.g. by
+    /// ANDing the control fields and keeping the support fields as requested.
+    pub fn negotiate(
+        &self,
+        capabilities: &[u32],
+        granted: impl Fn(&[u32], &[u32]) -> Vec<u32>,
+    ) -> Result<Vec<u32>, OscError> {
+        let offered = self.query(capabilities)?;
+
+        self.commit(&granted(capabilities, &offered))
+    }
+
+    fn evaluate(&self, query: bool, capabilities: &[u32]) -> Result<Vec<u32>, OscError> {
+        let status = match query {
+            true => OSC_QUERY_ENABLE,
+            false => 0,
+        };
+
+        let buffer = core::iter::once(status)
+            .chain(capabilities.iter().copied())
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<_>>();
+
+        let result = self
+            .device
+            .evaluate(
+                "_OSC",
+                &[
+                    Object::Buffer(self.uuid.as_bytes().to_vec()),
+                    Object::Integer(self.revision),
+                    Object::Integer(capabilities.len() as u64 + 1),
+                    Object::Buffer(buffer),
+                ],
+            )
+            .map_err(OscError::Status)?;
+
+        let dwords = match result {
+            Some(Object::Buffer(bytes)) if bytes.len() >= 4 => bytes
+                .chunks_exact(4)
+                .map(|dword| u32::from_le_bytes(dword.try_into().unwrap()))
+                .collect::<Vec<_>>(),
+            _ => return Err(OscError::Status(AE_TYPE)),
+        };
+
+        let status = dwords[0];
+        let returned = dwords[1..].to_vec();
+
+        if status & OSC_INVALID_UUID_ERROR != 0 {
+            return Err(OscError::UnrecognizedUuid);
+        }
+
+        if status & OSC_INVALID_REVISION_ERROR != 0 {
+            return Err(OscError::UnrecognizedRevision);
+        }
+
+        // Masking is how a query reports what would be granted
+        if status & OSC_CAPABILITIES_MASK_ERROR != 0 {
+            return match query {
+                true => Ok(returned),
+                false => Err(OscError::CapabilitiesMasked(returned)),
+            };
+        }
+
+        if status & OSC_REQUEST_ERROR != 0 {
+            return Err(OscError::Failure);
+        }
+
+        Ok(returned)
+    }
+}
+
+/// Features the OS supports, from the platform-wide `_OSC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PlatformCapabilities(pub u32);
+
+impl PlatformCapabilities {
+    /// The processor aggregator device.
+    pub const PROCESSOR_AGGREGATOR: Self = Self(1 << 0);
+    /// `_OST` evaluation after `_PPC` changes.
+    pub const PPC_OST: Self = Self(1 << 1);
+    /// `_PR3` and D3cold.
+    pub const PR3: Self = Self(1 << 2);
+    /// `_OST` evaluation after hotplug events.
+    pub const HOTPLUG_OST: Self = Self(1 << 3);
+    /// The ACPI platform error interfaces.
+    pub const APEI: Self = Self(1 << 4);
+    /// `_CPC` revision 1.
+    pub const CPPC: Self = Self(1 << 5);
+    /// `_CPC` revision 2 and later.
+    pub const CPPC_V2: Self = Self(1 << 6);
+    /// Platform coordinated low power idle states.
+    pub const PLATFORM_COORDINATED_LPI: Self = Self(1 << 7);
+    /// OS initiated low power idle states.
+    pub const OS_INITIATED_LPI: Self = Self(1 << 8);
+    /// Processors with different highest performance.
+    pub const CPPC_DIVERSE_HIGHEST: Self = Self(1 << 12);
+    /// Generic initiator affinity in the SRAT.
+    pub const GENERIC_INITIATOR: Self = Self(1 << 13);
+    /// `_CPC` registers in any address space the OS supports.
+    pub const CPPC_FLEXIBLE_ADDRESS_SPACE: Self = Self(1 << 14);
+    /// Native USB4 control.
+    pub const NATIVE_USB4: Self = Self(1 << 18);
+    /// Platform runtime mechanism handlers.
+    pub const PRM: Self = Self(1 << 21);
+    /// Fixed hardware operation regions.
+    pub const FFH_OPERATION_REGION: Self = Self(1 << 22);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for PlatformCapabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Features of PCI host bridges the OS supports, the support field of their `_OSC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PciSupport(pub u32);
+
+impl PciSupport {
+    /// Extended configuration space through ECAM.
+    pub const EXTENDED_CONFIG: Self = Self(1 << 0);
+    pub const ASPM: Self = Self(1 << 1);
+    pub const CLOCK_PM: Self = Self(1 << 2);
+    pub const SEGMENTS: Self = Self(1 << 3);
+    pub const MSI: Self = Self(1 << 4);
+    /// Error disconnect recover.
+    pub const EDR: Self = Self(1 << 7);
+    /// `_HPX` type 3 records.
+    pub const HPX_TYPE_3: Self = Self(1 << 8);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for PciSupport {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// PCIe features whose control the OS asks for, the control field of the PCI host bridge
+/// `_OSC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PciControl(pub u32);
+
+impl PciControl {
+    pub const NATIVE_HOTPLUG: Self = Self(1 << 0);
+    /// Standard hot plug controller hotplug.
+    pub const SHPC_HOTPLUG: Self = Self(1 << 1);
+    pub const PME: Self = Self(1 << 2);
+    pub const AER: Self = Self(1 << 3);
+    /// The PCIe capability structure.
+    pub const CAPABILITY_STRUCTURE: Self = Self(1 << 4);
+    pub const LTR: Self = Self(1 << 5);
+    pub const DPC: Self = Self(1 << 7);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for PciControl {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Negotiates the platform-wide `_OSC` of `\_SB`, returning the capabilities the platform
+/// acknowledged.
+pub fn negotiate_platform(
+    capabilities: PlatformCapabilities,
+) -> Result<PlatformCapabilities, OscError> {
+    let system_bus = Handle::from_path("\\_SB").map_err(OscError::Status)?;
+    let osc = Osc::new(system_bus, PLATFORM_UUID, OSC_REVISION);
+
+    let granted = osc.negotiate(&[capabilities.0], |requested, offered| {
+        vec![requested[0] & offered.first().copied().unwrap_or(0)]
+    })?;
+
+    Ok(PlatformCapabilities(granted.first().copied().unwrap_or(0)))
+}
+
+/// Negotiates the `_OSC` of a PCI host bridge, returning the PCIe features the OS now
+/// controls.
+///
+/// Firmware usually only grants control if `support` includes extended configuration
+/// space, segments and MSI. Features it keeps are still handled by the firmware.
+pub fn negotiate_pci_host_bridge(
+    bridge: Handle,
+    support: PciSupport,
+    control: PciControl,
+) -> Result<PciControl, OscError> {
+    let osc = Osc::new(bridge, PCI_HOST_BRIDGE_UUID, OSC_REVISION);
+
+    let granted = osc.negotiate(&[support.0, control.0], |requested, offered| {
+        vec![
+            requested[0],
+            requested[1] & offered.get(1).copied().unwrap_or(0),
+        ]
+    })?;
+
+    Ok(PciControl(granted.get(1).copied().unwrap_or(0)))
+}
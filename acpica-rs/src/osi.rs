@@ -0,0 +1,203 @@
+//! The interfaces `_OSI` claims to support.
+//!
+//! Firmware calls `_OSI("Windows 2015")` and similar to find out which OS it runs on, and
+//! often only enables features, or works around bugs, for the Windows versions it was
+//! tested with. ACPICA answers from a list of interface strings, which by default claims
+//! every Windows version it knows. The list can be changed at any time, but AML usually
+//! caches the answers while the namespace is initialized, so it should be set up before
+//! [`AcpiInitializeObjects`](acpica_sys::AcpiInitializeObjects) runs.
+
+use core::ffi::{c_char, CStr};
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+use spin::Mutex;
+
+use crate::{namespace::c_string, to_result, AE_ALREADY_EXISTS, AE_NOT_EXIST};
+
+/// The handler consulted for each `_OSI` query.
+static INTERFACE_HANDLER: Mutex<Option<Box<dyn Fn(&str, bool) -> bool + Send + Sync>>> =
+    Mutex::new(None);
+
+/// A Windows version, as claimed through `_OSI`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Windows {
+    Win2000,
+    Xp,
+    XpSp1,
+    Server2003,
+    XpSp2,
+    Server2003Sp1,
+    Vista,
+    Server2008,
+    VistaSp1,
+    VistaSp2,
+    Win7,
+    Win8,
+    Win8_1,
+    Win10,
+    Win10Rs1,
+    Win10Rs2,
+    Win10Rs3,
+    Win10Rs4,
+    Win10Rs5,
+    Win10_19H1,
+    Win10_20H1,
+    Win11,
+    Win11_22H2,
+}
+
+impl Windows {
+    /// Every version ACPICA knows, oldest first.
+    pub const ALL: [Self; 23] = [
+        Self::Win2000,
+        Self::Xp,
+        Self::XpSp1,
+        Self::Server2003,
+        Self::XpSp2,
+        Self::Server2003Sp1,
+        Self::Vista,
+        Self::Server2008,
+        Self::VistaSp1,
+        Self::VistaSp2,
+        Self::Win7,
+        Self::Win8,
+        Self::Win8_1,
+        Self::Win10,
+        Self::Win10Rs1,
+        Self::Win10Rs2,
+        Self::Win10Rs3,
+        Self::Win10Rs4,
+        Self::Win10Rs5,
+        Self::Win10_19H1,
+        Self::Win10_20H1,
+        Self::Win11,
+        Self::Win11_22H2,
+    ];
+
+    /// The string passed to `_OSI` for the version.
+    pub const fn interface_name(self) -> &'static str {
+        match self {
+            Self::Win2000 => "Windows 2000",
+            Self::Xp => "Windows 2001",
+            Self::XpSp1 => "Windows 2001 SP1",
+            Self::Server2003 => "Windows 2001.1",
+            Self::XpSp2 => "Windows 2001 SP2",
+            Self::Server2003Sp1 => "Windows 2001.1 SP1",
+            Self::Vista => "Windows 2006",
+            Self::Server2008 => "Windows 2006.1",
+            Self::VistaSp1 => "Windows 2006 SP1",
+            Self::VistaSp2 => "Windows 2006 SP2",
+            Self::Win7 => "Windows 2009",
+            Self::Win8 => "Windows 2012",
+            Self::Win8_1 => "Windows 2013",
+            Self::Win10 => "Windows 2015",
+            Self::Win10Rs1 => "Windows 2016",
+            Self::Win10Rs2 => "Windows 2017",
+            Self::Win10Rs3 => "Windows 2017.2",
+            Self::Win10Rs4 => "Windows 2018",
+            Self::Win10Rs5 => "Windows 2018.2",
+            Self::Win10_19H1 => "Windows 2019",
+            Self::Win10_20H1 => "Windows 2020",
+            Self::Win11 => "Windows 2021",
+            Self::Win11_22H2 => "Windows 2022",
+        }
+    }
+}
+
+/// Adds `name` to the interfaces `_OSI` claims, or re-enables one of the defaults.
+///
+/// Fails with `AE_ALREADY_EXISTS` if it is already claimed.
+pub fn install_interface(name: &str) -> Result<(), ACPI_STATUS> {
+    let mut name = c_string(name);
+    to_result(unsafe { AcpiInstallInterface(name.as_mut_ptr()) })
+}
+
+/// Stops `_OSI` from claiming `name`.
+///
+/// Fails with `AE_NOT_EXIST` if it isn't claimed.
+pub fn remove_interface(name: &str) -> Result<(), ACPI_STATUS> {
+    let mut name = c_string(name);
+    to_result(unsafe { AcpiRemoveInterface(name.as_mut_ptr()) })
+}
+
+/// Claims the Windows versions up to and including `newest`, and none after it.
+///
+/// Useful when firmware misbehaves with the newest versions.
+pub fn claim_windows_up_to(newest: Windows) -> Result<(), ACPI_STATUS> {
+    for version in Windows::ALL {
+        let result = match version <= newest {
+            true => install_interface(version.interface_name()),
+            false => remove_interface(version.interface_name()),
+        };
+
+        match result {
+            Ok(()) | Err(AE_ALREADY_EXISTS) | Err(AE_NOT_EXIST) => {}
+            Err(status) => return Err(status),
+        }
+    }
+
+    Ok(())
+}
+
+/// Claims or stops claiming every Windows version ACPICA knows.
+pub fn set_vendor_strings(enabled: bool) -> Result<(), ACPI_STATUS> {
+    update_interfaces(ACPI_VENDOR_STRINGS, enabled)
+}
+
+/// Claims or stops claiming the default feature group strings, e.g. `Module Device` or
+/// `3.0 Thermal Model`.
+pub fn set_feature_strings(enabled: bool) -> Result<(), ACPI_STATUS> {
+    update_interfaces(ACPI_FEATURE_STRINGS, enabled)
+}
+
+fn update_interfaces(strings: u32, enabled: bool) -> Result<(), ACPI_STATUS> {
+    let action = match enabled {
+        true => ACPI_ENABLE_INTERFACES | strings,
+        false => ACPI_DISABLE_INTERFACES | strings,
+    };
+
+    to_result(unsafe { AcpiUpdateInterfaces(action as u8) })
+}
+
+/// Calls `handler` with the interface name and ACPICA's answer for every `_OSI` query, e.g.
+/// to log which versions the firmware checks for.
+///
+/// Returning `true` claims the interface even if it isn't in the list. Returning `false`
+/// keeps ACPICA's answer; use [`remove_interface`] to deny an interface. Replaces the
+/// handler set before. The handler must not call this function itself.
+pub fn set_interface_handler<F>(handler: F) -> Result<(), ACPI_STATUS>
+where
+    F: Fn(&str, bool) -> bool + Send + Sync + 'static,
+{
+    let mut current = INTERFACE_HANDLER.lock();
+
+    if current.is_none() {
+        to_result(unsafe { AcpiInstallInterfaceHandler(Some(interface_handler)) })?;
+    }
+
+    *current = Some(Box::new(handler));
+
+    Ok(())
+}
+
+/// Removes the handler set with [`set_interface_handler`].
+pub fn remove_interface_handler() -> Result<(), ACPI_STATUS> {
+    let mut current = INTERFACE_HANDLER.lock();
+
+    if current.is_some() {
+        to_result(unsafe { AcpiInstallInterfaceHandler(None) })?;
+        *current = None;
+    }
+
+    Ok(())
+}
+
+unsafe extern "C" fn interface_handler(InterfaceName: ACPI_STRING, Supported: u32) -> u32 {
+    let name = CStr::from_ptr(InterfaceName as *const c_char).to_string_lossy();
+
+    match INTERFACE_HANDLER.lock().as_ref() {
+        Some(handler) => handler(&name, Supported != 0) as u32,
+        None => 0,
+    }
+}
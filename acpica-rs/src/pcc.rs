@@ -0,0 +1,363 @@
+//! Platform Communication Channel (PCC) mailbox driver.
+//!
+//! A PCC subspace is a shared memory region plus a doorbell the OS rings to hand a command to
+//! the platform, and a way for the platform to report completion: either a status bit in the
+//! shared memory or a dedicated command complete register. [`PccMailbox`] owns every subspace
+//! described by the PCCT and can also service PCC operation regions accessed from AML, which
+//! e.g. CPPC on servers depends on.
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::{
+    current_os_services_implementation,
+    mapping::PhysicalMapping,
+    namespace::Handle,
+    region::{
+        self, AddressSpace, RegionAccess, RegionActivation, RegionHandler,
+        RegionHandlerRegistration, RegionRequest,
+    },
+    tables::pcct::{PccSubspace, PccSubspaceType, PccWriteRegister, Pcct},
+    AE_BAD_PARAMETER, AE_IO_ERROR, AE_NOT_FOUND, AE_TIME,
+};
+
+/// "PCC" followed by the subspace ID.
+const PCC_SIGNATURE: u32 = 0x5043_4300;
+
+/// Offsets into the generic shared memory header.
+const GENERIC_COMMAND_OFFSET: usize = 4;
+const GENERIC_STATUS_OFFSET: usize = 6;
+
+/// Offsets into the extended shared memory header.
+const EXTENDED_FLAGS_OFFSET: usize = 4;
+const EXTENDED_LENGTH_OFFSET: usize = 8;
+const EXTENDED_COMMAND_OFFSET: usize = 12;
+
+/// Size of the shared memory headers, after which the communication space starts.
+const GENERIC_HEADER_SIZE: usize = 8;
+const EXTENDED_HEADER_SIZE: usize = 16;
+
+/// Bits of the generic shared memory status field.
+const STATUS_COMMAND_COMPLETE: u16 = 1 << 0;
+const STATUS_ERROR: u16 = 1 << 2;
+
+/// Commands are given this many times the nominal latency of the subspace to complete.
+const COMPLETION_RETRIES: u64 = 500;
+
+/// A mapped PCC subspace.
+pub struct PccChannel {
+    subspace: PccSubspace,
+    shared_memory: PhysicalMapping,
+    /// Timer value (in 100ns units) at which the last command completed.
+    last_completion: Option<u64>,
+}
+
+impl PccChannel {
+    /// Maps the shared memory region of a subspace.
+    pub fn new(subspace: PccSubspace) -> Result<Self, ACPI_STATUS> {
+        let shared_memory = PhysicalMapping::new(subspace.base_address, subspace.length as usize)?;
+
+        Ok(Self {
+            subspace,
+            shared_memory,
+            last_completion: None,
+        })
+    }
+
+    pub fn subspace(&self) -> &PccSubspace {
+        &self.subspace
+    }
+
+    /// Size of the shared memory region in bytes.
+    pub fn shared_memory_len(&self) -> usize {
+        self.shared_memory.len()
+    }
+
+    /// Offset of the communication space, which follows the shared memory header.
+    pub fn communication_space_offset(&self) -> usize {
+        match self.subspace.subspace_type {
+            PccSubspaceType::Generic
+            | PccSubspaceType::HwReduced
+            | PccSubspaceType::HwReducedType2 => GENERIC_HEADER_SIZE,
+            PccSubspaceType::ExtendedInitiator | PccSubspaceType::ExtendedResponder => {
+                EXTENDED_HEADER_SIZE
+            }
+            PccSubspaceType::HwRegisters => 0,
+        }
+    }
+
+    /// Copies bytes out of the shared memory region.
+    pub fn read_shared_memory(&self, offset: usize, buffer: &mut [u8]) -> Result<(), ACPI_STATUS> {
+        self.check_bounds(offset, buffer.len())?;
+
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte =
+                unsafe { core::ptr::read_volatile(self.shared_memory.as_ptr().add(offset + i)) };
+        }
+
+        Ok(())
+    }
+
+    /// Copies bytes into the shared memory region.
+    pub fn write_shared_memory(&mut self, offset: usize, data: &[u8]) -> Result<(), ACPI_STATUS> {
+        self.check_bounds(offset, data.len())?;
+
+        for (i, byte) in data.iter().enumerate() {
+            unsafe {
+                core::ptr::write_volatile(self.shared_memory.as_ptr().add(offset + i), *byte)
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Writes the shared memory header for `command` and hands it to the platform.
+    ///
+    /// * `command` - The command code.
+    /// * `payload_length` - Length of the payload following the header. Only used by the
+    ///   extended subspace types, which carry it in their header.
+    ///
+    /// The payload must already have been written with [`Self::write_shared_memory`].
+    pub fn send_command(&mut self, command: u32, payload_length: u32) -> Result<(), ACPI_STATUS> {
+        let signature = PCC_SIGNATURE | self.subspace.id as u32;
+
+        match self.subspace.subspace_type {
+            PccSubspaceType::Generic
+            | PccSubspaceType::HwReduced
+            | PccSubspaceType::HwReducedType2 => {
+                self.write_shared_memory(0, &signature.to_le_bytes())?;
+                self.write_shared_memory(GENERIC_COMMAND_OFFSET, &(command as u16).to_le_bytes())?;
+                self.write_shared_memory(GENERIC_STATUS_OFFSET, &0u16.to_le_bytes())?;
+            }
+            PccSubspaceType::ExtendedInitiator | PccSubspaceType::ExtendedResponder => {
+                // The length covers the command field as well as the payload
+                self.write_shared_memory(0, &signature.to_le_bytes())?;
+                self.write_shared_memory(EXTENDED_FLAGS_OFFSET, &0u32.to_le_bytes())?;
+                self.write_shared_memory(
+                    EXTENDED_LENGTH_OFFSET,
+                    &(payload_length + 4).to_le_bytes(),
+                )?;
+                self.write_shared_memory(EXTENDED_COMMAND_OFFSET, &command.to_le_bytes())?;
+            }
+            PccSubspaceType::HwRegisters => {}
+        }
+
+        self.ring_doorbell_and_wait()
+    }
+
+    /// Hands a complete shared memory image to the platform and reads the response back
+    /// into `buffer`.
+    ///
+    /// This is how PCC operation regions are serviced: AML builds the whole image, header
+    /// included.
+    pub fn exchange(&mut self, buffer: &mut [u8]) -> Result<(), ACPI_STATUS> {
+        self.write_shared_memory(0, buffer)?;
+        self.ring_doorbell_and_wait()?;
+        self.read_shared_memory(0, buffer)
+    }
+
+    /// Acknowledges the platform interrupt, for subspaces that have an ack register.
+    pub fn acknowledge_interrupt(&self) -> Result<(), ACPI_STATUS> {
+        match &self.subspace.platform_ack {
+            Some(ack) => read_modify_write(ack),
+            None => Ok(()),
+        }
+    }
+
+    fn ring_doorbell_and_wait(&mut self) -> Result<(), ACPI_STATUS> {
+        let os_services = current_os_services_implementation().unwrap();
+
+        // Respect the minimum turnaround time between two commands
+        if let Some(last_completion) = self.last_completion {
+            let elapsed = os_services.get_timer().saturating_sub(last_completion) / 10;
+            let turnaround = self.subspace.min_turnaround_time as u64;
+
+            if elapsed < turnaround {
+                os_services.stall((turnaround - elapsed) as u32);
+            }
+        }
+
+        read_modify_write(&self.subspace.doorbell)?;
+
+        let result = self.wait_for_completion();
+        self.last_completion = Some(os_services.get_timer());
+
+        result
+    }
+
+    fn wait_for_completion(&self) -> Result<(), ACPI_STATUS> {
+        let os_services = current_os_services_implementation().unwrap();
+        let timeout = (self.subspace.latency.max(1) as u64) * COMPLETION_RETRIES;
+
+        for _ in 0..timeout {
+            if self.command_complete()? {
+                return self.check_error();
+            }
+
+            os_services.stall(1);
+        }
+
+        Err(AE_TIME)
+    }
+
+    fn command_complete(&self) -> Result<bool, ACPI_STATUS> {
+        match &self.subspace.command_complete {
+            Some(complete) => Ok(complete.register.read()? & complete.mask != 0),
+            None => Ok(self.generic_status()? & STATUS_COMMAND_COMPLETE != 0),
+        }
+    }
+
+    fn check_error(&self) -> Result<(), ACPI_STATUS> {
+        match &self.subspace.error_status {
+            Some(error) => {
+                let value = error.register.read()?;
+
+                if value & error.mask != 0 {
+                    // The error bits are cleared by the OS
+                    error.register.write(value & !error.mask)?;
+                    return Err(AE_IO_ERROR);
+                }
+
+                Ok(())
+            }
+            None if self.subspace.command_complete.is_none() => {
+                match self.generic_status()? & STATUS_ERROR {
+                    0 => Ok(()),
+                    _ => Err(AE_IO_ERROR),
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn generic_status(&self) -> Result<u16, ACPI_STATUS> {
+        let mut status = [0; 2];
+        self.read_shared_memory(GENERIC_STATUS_OFFSET, &mut status)?;
+
+        Ok(u16::from_le_bytes(status))
+    }
+
+    fn check_bounds(&self, offset: usize, length: usize) -> Result<(), ACPI_STATUS> {
+        match offset.checked_add(length) {
+            Some(end) if end <= self.shared_memory.len() => Ok(()),
+            _ => Err(AE_BAD_PARAMETER),
+        }
+    }
+}
+
+/// Clears the bits outside the preserve mask, then sets the bits in the set mask.
+fn read_modify_write(register: &PccWriteRegister) -> Result<(), ACPI_STATUS> {
+    let value = register.register.read()?;
+
+    register
+        .register
+        .write((value & register.preserve_mask) | register.set_mask)
+}
+
+/// All PCC subspaces of the platform.
+///
+/// Subspaces are mapped the first time they are used.
+pub struct PccMailbox {
+    pcct: Pcct,
+    channels: Vec<Mutex<Option<PccChannel>>>,
+}
+
+impl PccMailbox {
+    /// Creates a mailbox for the subspaces described by the PCCT.
+    pub fn new() -> Result<Self, ACPI_STATUS> {
+        Ok(Self::from_pcct(Pcct::get()?))
+    }
+
+    /// Creates a mailbox for the subspaces of an already parsed PCCT.
+    pub fn from_pcct(pcct: Pcct) -> Self {
+        let channels = pcct.subspaces.iter().map(|_| Mutex::new(None)).collect();
+
+        Self { pcct, channels }
+    }
+
+    pub fn pcct(&self) -> &Pcct {
+        &self.pcct
+    }
+
+    /// Runs `f` with exclusive access to the channel of subspace `id`.
+    pub fn with_channel<R>(
+        &self,
+        id: u8,
+        f: impl FnOnce(&mut PccChannel) -> R,
+    ) -> Result<R, ACPI_STATUS> {
+        let index = self
+            .pcct
+            .subspaces
+            .iter()
+            .position(|subspace| subspace.id == id)
+            .ok_or(AE_NOT_FOUND)?;
+
+        let mut channel = self.channels[index].lock();
+        if channel.is_none() {
+            *channel = Some(PccChannel::new(self.pcct.subspaces[index])?);
+        }
+
+        Ok(f(channel.as_mut().unwrap()))
+    }
+
+    /// Installs this mailbox as the handler for PCC operation regions in the whole namespace.
+    pub fn install_address_space_handler(
+        &'static self,
+    ) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+        region::install_handler(
+            Handle::root(),
+            AddressSpace::PlatformCommunication,
+            PccRegionHandler { mailbox: self },
+        )
+    }
+}
+
+/// Services PCC operation regions through a [`PccMailbox`].
+struct PccRegionHandler {
+    mailbox: &'static PccMailbox,
+}
+
+/// The PCC info ACPICA hands out when a region is activated.
+struct PccRegion {
+    subspace_id: u8,
+    buffer: *mut u8,
+    length: usize,
+}
+
+// The internal buffer belongs to the region and is only touched with its channel locked
+unsafe impl Send for PccRegion {}
+unsafe impl Sync for PccRegion {}
+
+impl RegionHandler for PccRegionHandler {
+    type Context = PccRegion;
+
+    fn activate(&self, activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        // Every region gets its own copy, the info is overwritten by the next activation
+        let info = activation.pcc().ok_or(AE_BAD_PARAMETER)?;
+
+        Ok(PccRegion {
+            subspace_id: info.SubspaceId,
+            buffer: info.InternalBuffer,
+            length: info.Length as usize,
+        })
+    }
+
+    fn access(
+        &self,
+        region: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        // Reads are served from the internal buffer by ACPICA, only writes of the command
+        // field reach the handler
+        if request.access() != RegionAccess::Write {
+            return Ok(());
+        }
+
+        self.mailbox.with_channel(region.subspace_id, |channel| {
+            let buffer = unsafe { core::slice::from_raw_parts_mut(region.buffer, region.length) };
+
+            channel.exchange(buffer)
+        })?
+    }
+}
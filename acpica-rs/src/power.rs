@@ -0,0 +1,320 @@
+//! System sleep states.
+//!
+//! A transition to a sleep state runs in steps: [`enter_sleep_state_prep`] evaluates `_PTS`,
+//! then [`enter_sleep_state`] writes the sleep registers with interrupts disabled, calling
+//! [`AcpicaOsServices::enter_sleep`](crate::AcpicaOsServices::enter_sleep) right before.
+//! After waking up, [`leave_sleep_state_prep`] and [`leave_sleep_state`] restore the
+//! registers and run `_WAK`. [`sleep`] and [`power_off`] do all of this in one call.
+
+use core::convert::Infallible;
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::{current_os_services_implementation, to_result, AE_BAD_PARAMETER, AE_ERROR};
+
+/// The keyboard controller command and status port, and the command that pulses the reset
+/// line.
+const KBC_COMMAND_PORT: u16 = 0x64;
+const KBC_STATUS_IBF: u8 = 1 << 1;
+const KBC_COMMAND_RESET: u8 = 0xFE;
+
+/// The PCI reset control register and its bits.
+const PCI_RESET_CONTROL_PORT: u16 = 0xCF9;
+const PCI_RESET_SYS_RST: u8 = 1 << 1;
+const PCI_RESET_RST_CPU: u8 = 1 << 2;
+
+/// How long to wait for a reset to take effect before trying the next method, in
+/// microseconds.
+const RESET_TIMEOUT: u32 = 50_000;
+
+/// The methods tried, in order, when the reset register is missing or did not work.
+static RESET_FALLBACKS: Mutex<Vec<ResetMethod>> = Mutex::new(Vec::new());
+
+/// A system sleep state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SleepState {
+    /// Working.
+    S0,
+    /// Power on suspend: the processors stop, everything else keeps its context.
+    S1,
+    /// Like S1, but the processor and cache context is lost.
+    S2,
+    /// Suspend to RAM.
+    S3,
+    /// Suspend to disk. The OS saves memory itself before entering it.
+    S4,
+    /// Soft off.
+    S5,
+}
+
+/// The values written to the `SLP_TYP` fields of the PM1a and PM1b control registers to enter
+/// a sleep state, from the `\_Sx` package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SleepTypeData {
+    pub slp_typ_a: u8,
+    pub slp_typ_b: u8,
+}
+
+impl SleepState {
+    pub const ALL: [Self; 6] = [Self::S0, Self::S1, Self::S2, Self::S3, Self::S4, Self::S5];
+
+    /// Returns the `ACPI_STATE_S*` number of the state.
+    pub const fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Reads the sleep type values of the state, failing with `AE_NOT_FOUND` if the
+    /// platform does not support it.
+    pub fn type_data(self) -> Result<SleepTypeData, ACPI_STATUS> {
+        let mut slp_typ_a = 0;
+        let mut slp_typ_b = 0;
+
+        to_result(unsafe { AcpiGetSleepTypeData(self.id(), &mut slp_typ_a, &mut slp_typ_b) })?;
+
+        Ok(SleepTypeData {
+            slp_typ_a,
+            slp_typ_b,
+        })
+    }
+
+    pub fn is_supported(self) -> bool {
+        self.type_data().is_ok()
+    }
+}
+
+/// A device power state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceState {
+    /// Fully on.
+    D0,
+    D1,
+    D2,
+    /// Off, but still powered, so the device can be found on its bus.
+    D3Hot,
+    /// Off, with its power resources turned off as well.
+    D3Cold,
+}
+
+impl DeviceState {
+    pub const ALL: [Self; 5] = [Self::D0, Self::D1, Self::D2, Self::D3Hot, Self::D3Cold];
+
+    /// Returns the `ACPI_STATE_D*` number of the state, 4 for D3cold.
+    pub const fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the state for a number as returned by `_PSC` or `_SxD`, in which 3 means
+    /// D3hot.
+    pub const fn from_id(id: u64) -> Option<Self> {
+        match id {
+            0 => Some(Self::D0),
+            1 => Some(Self::D1),
+            2 => Some(Self::D2),
+            3 => Some(Self::D3Hot),
+            4 => Some(Self::D3Cold),
+            _ => None,
+        }
+    }
+
+    /// Returns the state number as passed to AML methods, which don't tell D3hot and D3cold
+    /// apart.
+    pub const fn aml_id(self) -> u8 {
+        match self {
+            Self::D3Cold => 3,
+            state => state as u8,
+        }
+    }
+}
+
+/// Returns the sleep states the platform supports, S0 included.
+pub fn supported_states() -> Vec<SleepState> {
+    SleepState::ALL
+        .into_iter()
+        .filter(|state| state.is_supported())
+        .collect()
+}
+
+/// Runs `_PTS` and sets the system status indicator for a transition to `state`.
+pub fn enter_sleep_state_prep(state: SleepState) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiEnterSleepStatePrep(state.id()) })
+}
+
+/// Enters `state`. Must be called with interrupts disabled.
+///
+/// For S1 to S3, this returns once the system has woken up again, unless the OS resumes
+/// somewhere else from the firmware waking vector.
+pub fn enter_sleep_state(state: SleepState) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiEnterSleepState(state.id()) })
+}
+
+/// Restores the sleep registers after waking up from `state`. Must be called with
+/// interrupts disabled.
+pub fn leave_sleep_state_prep(state: SleepState) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiLeaveSleepStatePrep(state.id()) })
+}
+
+/// Runs `_WAK` and re-enables runtime GPEs after waking up from `state`.
+pub fn leave_sleep_state(state: SleepState) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiLeaveSleepState(state.id()) })
+}
+
+/// Sets the address the firmware jumps to in real mode when waking up from S3, and the
+/// address it jumps to in 64-bit mode if it supports that, or zero.
+pub fn set_firmware_waking_vector(address: u64, address64: u64) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiSetFirmwareWakingVector(address, address64) })
+}
+
+/// Puts the system into `state` and wakes it up again. Must be called with every other
+/// processor offline.
+///
+/// `set_interrupts(false)` and `set_interrupts(true)` are called around the steps that need
+/// interrupts disabled; `_PTS` and `_WAK` run with them enabled. The OS saves and restores
+/// its own state in [`AcpicaOsServices::enter_sleep`](crate::AcpicaOsServices::enter_sleep).
+pub fn sleep(state: SleepState, set_interrupts: impl Fn(bool)) -> Result<(), ACPI_STATUS> {
+    if state == SleepState::S0 || state == SleepState::S5 {
+        return Err(AE_BAD_PARAMETER);
+    }
+
+    enter_sleep_state_prep(state)?;
+
+    set_interrupts(false);
+    let result = enter_sleep_state(state);
+
+    // Also undoes _PTS if entering the state failed
+    let prep_result = leave_sleep_state_prep(state);
+    set_interrupts(true);
+
+    prep_result?;
+    leave_sleep_state(state)?;
+
+    result
+}
+
+/// Turns the system off by running `_PTS` and entering S5. Only returns if that failed.
+///
+/// `set_interrupts(false)` is called before entering S5, which needs interrupts disabled,
+/// and `set_interrupts(true)` if the system is still running afterwards.
+pub fn power_off(set_interrupts: impl Fn(bool)) -> Result<Infallible, ACPI_STATUS> {
+    enter_sleep_state_prep(SleepState::S5)?;
+
+    set_interrupts(false);
+    let result = enter_sleep_state(SleepState::S5);
+    set_interrupts(true);
+
+    result?;
+
+    // The enter_sleep hook took over, but the system is still running
+    Err(AE_ERROR)
+}
+
+/// A way to reset the system other than the FADT reset register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMethod {
+    /// Pulses the reset line through the 8042 keyboard controller.
+    KeyboardController,
+    /// Requests a hard reset through the PCI reset control register at I/O port `0xCF9`.
+    PciResetControl,
+    /// Calls an OS function that triple faults the processor, e.g. by loading an empty IDT
+    /// and raising an exception.
+    TripleFault(fn()),
+}
+
+impl ResetMethod {
+    fn reset(self) -> Result<(), ACPI_STATUS> {
+        match self {
+            Self::KeyboardController => {
+                // Wait for the controller to accept a command
+                for _ in 0..RESET_TIMEOUT / 10 {
+                    if read_port(KBC_COMMAND_PORT)? & KBC_STATUS_IBF == 0 {
+                        break;
+                    }
+
+                    stall(10);
+                }
+
+                write_port(KBC_COMMAND_PORT, KBC_COMMAND_RESET)
+            }
+            Self::PciResetControl => {
+                let control =
+                    read_port(PCI_RESET_CONTROL_PORT)? & !(PCI_RESET_SYS_RST | PCI_RESET_RST_CPU);
+
+                // The reset happens on the transition of RST_CPU to 1
+                write_port(PCI_RESET_CONTROL_PORT, control | PCI_RESET_SYS_RST)?;
+                stall(50);
+                write_port(
+                    PCI_RESET_CONTROL_PORT,
+                    control | PCI_RESET_SYS_RST | PCI_RESET_RST_CPU,
+                )
+            }
+            Self::TripleFault(triple_fault) => {
+                triple_fault();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Sets the methods [`reset`] tries, in order, when the reset register is not supported or
+/// the system is still running after writing it.
+pub fn set_reset_fallbacks(methods: &[ResetMethod]) {
+    *RESET_FALLBACKS.lock() = methods.to_vec();
+}
+
+/// Writes the reset value to the FADT reset register.
+///
+/// Fails with `AE_NOT_EXIST` if the FADT does not describe a reset register.
+pub fn reset_register() -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiReset() })
+}
+
+/// Resets the system through the FADT reset register, then through the fallbacks set with
+/// [`set_reset_fallbacks`]. Only returns if all of them failed.
+///
+/// The error is the one from the reset register: `AE_NOT_EXIST` if the platform has none.
+pub fn reset() -> Result<Infallible, ACPI_STATUS> {
+    let status = match reset_register() {
+        Ok(()) => {
+            stall(RESET_TIMEOUT);
+            AE_ERROR
+        }
+        Err(status) => status,
+    };
+
+    let fallbacks = RESET_FALLBACKS.lock().clone();
+
+    for method in fallbacks {
+        if method.reset().is_ok() {
+            stall(RESET_TIMEOUT);
+        }
+    }
+
+    Err(status)
+}
+
+fn stall(microseconds: u32) {
+    current_os_services_implementation()
+        .unwrap()
+        .stall(microseconds);
+}
+
+fn read_port(port: u16) -> Result<u8, ACPI_STATUS> {
+    let mut value = 0;
+
+    to_result(current_os_services_implementation().unwrap().read_port(
+        port as ACPI_IO_ADDRESS,
+        &mut value,
+        8,
+    ))?;
+
+    Ok(value as u8)
+}
+
+fn write_port(port: u16, value: u8) -> Result<(), ACPI_STATUS> {
+    to_result(current_os_services_implementation().unwrap().write_port(
+        port as ACPI_IO_ADDRESS,
+        value as u32,
+        8,
+    ))
+}
@@ -0,0 +1,171 @@
+//! Platform Runtime Mechanism (PRM) operation regions.
+//!
+//! PRM handlers are pieces of firmware, described by the PRMT, that replace SMM code. AML
+//! invokes them by writing a command and a handler GUID to a `PlatformRtMechanism` region.
+//! How a handler is actually called depends on the firmware runtime environment of the OS,
+//! so that part is left to a [`PrmDispatcher`].
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::{
+    namespace::Handle,
+    region::{
+        self, AddressSpace, RegionAccess, RegionActivation, RegionHandler,
+        RegionHandlerRegistration, RegionRequest,
+    },
+    tables::prmt::{PrmHandler, PrmModule, Prmt},
+};
+
+/// Size of the buffer AML exchanges with the handler.
+const PRM_BUFFER_SIZE: usize = 26;
+
+/// Offsets into the buffer.
+const PRM_STATUS_OFFSET: usize = 0;
+const PRM_EFI_STATUS_OFFSET: usize = 1;
+const PRM_COMMAND_OFFSET: usize = 9;
+const PRM_GUID_OFFSET: usize = 10;
+
+/// Commands AML can give.
+const PRM_CMD_RUN_SERVICE: u8 = 0;
+const PRM_CMD_START_TRANSACTION: u8 = 1;
+const PRM_CMD_END_TRANSACTION: u8 = 2;
+
+/// Status codes returned to AML.
+const PRM_HANDLER_SUCCESS: u8 = 0;
+const PRM_HANDLER_ERROR: u8 = 1;
+const PRM_INVALID_COMMAND: u8 = 2;
+const PRM_HANDLER_GUID_NOT_FOUND: u8 = 3;
+const PRM_UPDATE_LOCK_ALREADY_HELD: u8 = 4;
+const PRM_UPDATE_UNLOCK_WITHOUT_LOCK: u8 = 5;
+
+/// Calls PRM handlers in the firmware runtime environment.
+pub trait PrmDispatcher: Send + Sync + 'static {
+    /// Calls `handler` of `module` with its static data buffer, ACPI parameter buffer and
+    /// the MMIO ranges of the module, returning the EFI status of the call.
+    fn call(&self, module: &PrmModule, handler: &PrmHandler) -> Result<u64, ACPI_STATUS>;
+
+    /// Prevents `module` from being updated at runtime until the transaction ends.
+    fn start_transaction(&self, _module: &PrmModule) -> Result<(), ACPI_STATUS> {
+        Ok(())
+    }
+
+    /// Allows `module` to be updated again.
+    fn end_transaction(&self, _module: &PrmModule) -> Result<(), ACPI_STATUS> {
+        Ok(())
+    }
+}
+
+/// Installs a handler for `PlatformRtMechanism` regions in the whole namespace that looks
+/// handlers up in `prmt` and calls them through `dispatcher`.
+pub fn install_prm_handler<D: PrmDispatcher>(
+    prmt: Prmt,
+    dispatcher: D,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    let transactions = Mutex::new(prmt.modules.iter().map(|_| false).collect());
+
+    region::install_handler(
+        Handle::root(),
+        AddressSpace::PlatformRuntime,
+        PrmRegionHandler {
+            prmt,
+            dispatcher,
+            transactions,
+        },
+    )
+}
+
+struct PrmRegionHandler<D> {
+    prmt: Prmt,
+    dispatcher: D,
+    /// Whether a transaction is open, for each module.
+    transactions: Mutex<Vec<bool>>,
+}
+
+impl<D: PrmDispatcher> PrmRegionHandler<D> {
+    /// Runs a command, returning the PRM status and the EFI status for AML.
+    fn run(&self, command: u8, guid: &[u8; 16]) -> (u8, u64) {
+        let Some((module, handler)) = self.prmt.handler(guid) else {
+            return (PRM_HANDLER_GUID_NOT_FOUND, 0);
+        };
+
+        let index = self
+            .prmt
+            .modules
+            .iter()
+            .position(|candidate| core::ptr::eq(candidate, module))
+            .unwrap();
+
+        match command {
+            PRM_CMD_RUN_SERVICE => match self.dispatcher.call(module, handler) {
+                Ok(efi_status) => (PRM_HANDLER_SUCCESS, efi_status),
+                Err(_) => (PRM_HANDLER_ERROR, 0),
+            },
+            PRM_CMD_START_TRANSACTION => {
+                let mut transactions = self.transactions.lock();
+
+                if transactions[index] {
+                    return (PRM_UPDATE_LOCK_ALREADY_HELD, 0);
+                }
+
+                match self.dispatcher.start_transaction(module) {
+                    Ok(()) => {
+                        transactions[index] = true;
+                        (PRM_HANDLER_SUCCESS, 0)
+                    }
+                    Err(_) => (PRM_HANDLER_ERROR, 0),
+                }
+            }
+            PRM_CMD_END_TRANSACTION => {
+                let mut transactions = self.transactions.lock();
+
+                if !transactions[index] {
+                    return (PRM_UPDATE_UNLOCK_WITHOUT_LOCK, 0);
+                }
+
+                match self.dispatcher.end_transaction(module) {
+                    Ok(()) => {
+                        transactions[index] = false;
+                        (PRM_HANDLER_SUCCESS, 0)
+                    }
+                    Err(_) => (PRM_HANDLER_ERROR, 0),
+                }
+            }
+            _ => (PRM_INVALID_COMMAND, 0),
+        }
+    }
+}
+
+impl<D: PrmDispatcher> RegionHandler for PrmRegionHandler<D> {
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        // AML reads the results back from the buffer it wrote
+        if request.access() != RegionAccess::Write {
+            return Ok(());
+        }
+
+        let buffer = unsafe { request.buffer(PRM_BUFFER_SIZE) };
+
+        let command = buffer[PRM_COMMAND_OFFSET];
+        let mut guid = [0; 16];
+        guid.copy_from_slice(&buffer[PRM_GUID_OFFSET..PRM_GUID_OFFSET + 16]);
+
+        let (status, efi_status) = self.run(command, &guid);
+
+        buffer[PRM_STATUS_OFFSET] = status;
+        buffer[PRM_EFI_STATUS_OFFSET..PRM_COMMAND_OFFSET]
+            .copy_from_slice(&efi_status.to_le_bytes());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Processor performance and idle state objects.
+//!
+//! The objects below a processor device (`ACPI0007`) or `Processor` object describe its
+//! performance states (`_PSS`, `_PCT`, `_PPC`, `_PSD`) and idle states (`_CST`, `_CSD`,
+//! `_LPI`). They are decoded here into typed structures; driving the registers is left to
+//! the cpufreq and cpuidle drivers of the OS.
+
+use acpica_sys::*;
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    namespace::{integer, Handle, Object},
+    region::AddressSpace,
+    resources::generic_register,
+    tables::GenericAddress,
+    AE_NOT_FOUND, AE_TYPE,
+};
+
+/// The `_HID` of processor devices.
+pub const PROCESSOR_HID: &str = "ACPI0007";
+
+/// `CoordType` values of `_PSD` and `_CSD`.
+const COORDINATION_SW_ALL: u64 = 0xFC;
+const COORDINATION_SW_ANY: u64 = 0xFD;
+const COORDINATION_HW_ALL: u64 = 0xFE;
+
+/// Number of entries in a `_PSD` and a `_CSD` package.
+const PSD_ENTRIES: usize = 5;
+const CSD_ENTRIES: usize = 6;
+
+/// Indices into an `_LPI` state package.
+const LPI_MIN_RESIDENCY: usize = 0;
+const LPI_WAKEUP_LATENCY: usize = 1;
+const LPI_FLAGS: usize = 2;
+const LPI_ARCH_CONTEXT_LOST: usize = 3;
+const LPI_RESIDENCY_COUNTER_FREQUENCY: usize = 4;
+const LPI_ENABLED_PARENT_STATE: usize = 5;
+const LPI_ENTRY_METHOD: usize = 6;
+const LPI_RESIDENCY_COUNTER: usize = 7;
+const LPI_USAGE_COUNTER: usize = 8;
+const LPI_STATE_NAME: usize = 9;
+const LPI_STATE_ENTRIES: usize = 10;
+
+/// Bit of the `_LPI` flags that marks a state as enabled.
+const LPI_STATE_ENABLED: u32 = 1 << 0;
+
+/// A performance state from `_PSS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerformanceState {
+    /// Core frequency in MHz.
+    pub core_frequency: u32,
+    /// Typical power dissipation in milliwatts.
+    pub power: u32,
+    /// Worst case transition latency in microseconds.
+    pub transition_latency: u32,
+    /// Time bus masters are blocked during a transition, in microseconds.
+    pub bus_master_latency: u32,
+    /// Value to write to the control register to enter the state.
+    pub control: u64,
+    /// Value the status register reads once the state is entered.
+    pub status: u64,
+}
+
+/// The performance control and status registers from `_PCT`.
+///
+/// Registers in [`AddressSpace::FixedHardware`] mean architecture specific access, e.g.
+/// through MSRs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerformanceControl {
+    pub control: GenericAddress,
+    pub status: GenericAddress,
+}
+
+/// How the OS has to coordinate state changes of the processors in a domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coordination {
+    /// The OS changes the state of all processors in the domain.
+    SoftwareAll,
+    /// Changing the state of any processor in the domain changes it for all of them.
+    SoftwareAny,
+    /// Hardware coordinates the processors; the OS changes the state of each of them.
+    HardwareAll,
+}
+
+impl Coordination {
+    fn from_value(value: u64) -> Result<Self, ACPI_STATUS> {
+        match value {
+            COORDINATION_SW_ALL => Ok(Self::SoftwareAll),
+            COORDINATION_SW_ANY => Ok(Self::SoftwareAny),
+            COORDINATION_HW_ALL => Ok(Self::HardwareAll),
+            _ => Err(AE_TYPE),
+        }
+    }
+}
+
+/// A performance state dependency domain, from `_PSD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerformanceDependency {
+    pub domain: u32,
+    pub coordination: Coordination,
+    /// Number of processors in the domain.
+    pub processors: u32,
+}
+
+/// How an idle state is entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryMethod {
+    /// Reading the register, e.g. a `P_LVLx` I/O port.
+    Register(GenericAddress),
+    /// An architecture specific instruction sequence, e.g. `MWAIT` with the hint in the
+    /// address field on x86, or `PSCI_CPU_SUSPEND` on Arm.
+    FixedHardware(GenericAddress),
+    /// An integer, which on Arm is the PSCI suspend parameter.
+    Integer(u64),
+}
+
+impl EntryMethod {
+    fn from_register(register: GenericAddress) -> Self {
+        match AddressSpace::from_id(register.space_id) {
+            Some(AddressSpace::FixedHardware) => Self::FixedHardware(register),
+            _ => Self::Register(register),
+        }
+    }
+}
+
+/// An idle state from `_CST`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CState {
+    pub entry: EntryMethod,
+    /// The C state type, 1 to 3.
+    pub kind: u8,
+    /// Worst case entry and exit latency in microseconds.
+    pub latency: u32,
+    /// Average power consumption in milliwatts.
+    pub power: u32,
+}
+
+/// An idle state dependency domain, from `_CSD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleDependency {
+    pub domain: u32,
+    pub coordination: Coordination,
+    /// Number of processors in the domain.
+    pub processors: u32,
+    /// The index of the `_CST` state the dependency applies to, counting from 1.
+    pub index: u32,
+}
+
+/// A low power idle state from `_LPI`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpiState {
+    /// Minimum residency for the state to save energy, in microseconds.
+    pub min_residency: u32,
+    /// Worst case wakeup latency in microseconds.
+    pub wakeup_latency: u32,
+    pub enabled: bool,
+    /// Architecture specific flags telling which context is lost in the state.
+    pub arch_context_lost: u32,
+    /// Frequency of the residency counter in Hz, or zero if it counts at an unknown rate.
+    pub residency_counter_frequency: u32,
+    /// The deepest state of the parent level that may be entered together with this one,
+    /// counting from 1, or zero for none.
+    pub enabled_parent_state: u32,
+    pub entry: EntryMethod,
+    pub residency_counter: Option<GenericAddress>,
+    pub usage_counter: Option<GenericAddress>,
+    pub name: Option<String>,
+}
+
+/// The low power idle states of one level of the processor hierarchy, from `_LPI`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpiLevel {
+    pub revision: u16,
+    /// Identifies the level for composite states, e.g. in the PSCI power state parameter.
+    pub level_id: u64,
+    pub states: Vec<LpiState>,
+}
+
+/// A processor, or a processor container for `_LPI`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Processor {
+    handle: Handle,
+}
+
+impl Processor {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Evaluates `_PSS`, the performance states from fastest to slowest.
+    pub fn performance_states(&self) -> Result<Vec<PerformanceState>, ACPI_STATUS> {
+        self.handle
+            .evaluate_package("_PSS", &[])?
+            .iter()
+            .map(|state| {
+                let [frequency, power, latency, bus_master_latency, control, status] =
+                    integers(state)?;
+
+                Ok(PerformanceState {
+                    core_frequency: frequency as u32,
+                    power: power as u32,
+                    transition_latency: latency as u32,
+                    bus_master_latency: bus_master_latency as u32,
+                    control,
+                    status,
+                })
+            })
+            .collect()
+    }
+
+    /// Evaluates `_PCT`.
+    pub fn performance_control(&self) -> Result<PerformanceControl, ACPI_STATUS> {
+        match self.handle.evaluate_package("_PCT", &[])?.as_slice() {
+            [control, status] => Ok(PerformanceControl {
+                control: register(control)?,
+                status: register(status)?,
+            }),
+            _ => Err(AE_TYPE),
+        }
+    }
+
+    /// Evaluates `_PPC`, the index of the fastest performance state currently allowed.
+    ///
+    /// Without `_PPC`, all states are allowed. The platform sends notification `0x80` to the
+    /// processor when the limit changes.
+    pub fn performance_limit(&self) -> Result<usize, ACPI_STATUS> {
+        match self.handle.evaluate_integer("_PPC", &[]) {
+            Ok(limit) => Ok(limit as usize),
+            Err(AE_NOT_FOUND) => Ok(0),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Evaluates `_PSD`.
+    pub fn performance_dependency(&self) -> Result<PerformanceDependency, ACPI_STATUS> {
+        let psd = self.handle.evaluate_package("_PSD", &[])?;
+        let [entries, _revision, domain, coordination, processors] =
+            integers(psd.first().ok_or(AE_TYPE)?)?;
+
+        if entries as usize != PSD_ENTRIES {
+            return Err(AE_TYPE);
+        }
+
+        Ok(PerformanceDependency {
+            domain: domain as u32,
+            coordination: Coordination::from_value(coordination)?,
+            processors: processors as u32,
+        })
+    }
+
+    /// Evaluates `_CST`, the idle states from shallowest to deepest.
+    pub fn idle_states(&self) -> Result<Vec<CState>, ACPI_STATUS> {
+        let cst = self.handle.evaluate_package("_CST", &[])?;
+        let (count, states) = cst.split_first().ok_or(AE_TYPE)?;
+        let count = integer(count)? as usize;
+
+        states
+            .iter()
+            .take(count)
+            .map(|state| match state.as_package().ok_or(AE_TYPE)? {
+                [entry, kind, latency, power] => Ok(CState {
+                    entry: EntryMethod::from_register(register(entry)?),
+                    kind: integer(kind)? as u8,
+                    latency: integer(latency)? as u32,
+                    power: integer(power)? as u32,
+                }),
+                _ => Err(AE_TYPE),
+            })
+            .collect()
+    }
+
+    /// Evaluates `_CSD`.
+    pub fn idle_dependencies(&self) -> Result<Vec<IdleDependency>, ACPI_STATUS> {
+        self.handle
+            .evaluate_package("_CSD", &[])?
+            .iter()
+            .map(|dependency| {
+                let [entries, _revision, domain, coordination, processors, index] =
+                    integers(dependency)?;
+
+                if entries as usize != CSD_ENTRIES {
+                    return Err(AE_TYPE);
+                }
+
+                Ok(IdleDependency {
+                    domain: domain as u32,
+                    coordination: Coordination::from_value(coordination)?,
+                    processors: processors as u32,
+                    index: index as u32,
+                })
+            })
+            .collect()
+    }
+
+    /// Evaluates `_LPI`, the idle states of this level from shallowest to deepest.
+    pub fn low_power_idle_states(&self) -> Result<LpiLevel, ACPI_STATUS> {
+        let lpi = self.handle.evaluate_package("_LPI", &[])?;
+
+        let [revision, level_id, count, states @ ..] = lpi.as_slice() else {
+            return Err(AE_TYPE);
+        };
+
+        let states = states
+            .iter()
+            .take(integer(count)? as usize)
+            .map(lpi_state)
+            .collect::<Result<_, _>>()?;
+
+        Ok(LpiLevel {
+            revision: integer(revision)? as u16,
+            level_id: integer(level_id)?,
+            states,
+        })
+    }
+}
+
+/// Decodes a state package of `_LPI`.
+fn lpi_state(state: &Object) -> Result<LpiState, ACPI_STATUS> {
+    let state = state.as_package().ok_or(AE_TYPE)?;
+
+    if state.len() != LPI_STATE_ENTRIES {
+        return Err(AE_TYPE);
+    }
+
+    let value = |index: usize| integer(&state[index]).map(|value| value as u32);
+
+    let entry = match &state[LPI_ENTRY_METHOD] {
+        Object::Integer(value) => EntryMethod::Integer(*value),
+        entry => EntryMethod::from_register(register(entry)?),
+    };
+
+    Ok(LpiState {
+        min_residency: value(LPI_MIN_RESIDENCY)?,
+        wakeup_latency: value(LPI_WAKEUP_LATENCY)?,
+        enabled: value(LPI_FLAGS)? & LPI_STATE_ENABLED != 0,
+        arch_context_lost: value(LPI_ARCH_CONTEXT_LOST)?,
+        residency_counter_frequency: value(LPI_RESIDENCY_COUNTER_FREQUENCY)?,
+        enabled_parent_state: value(LPI_ENABLED_PARENT_STATE)?,
+        entry,
+        residency_counter: optional_register(&state[LPI_RESIDENCY_COUNTER]),
+        usage_counter: optional_register(&state[LPI_USAGE_COUNTER]),
+        name: state[LPI_STATE_NAME].as_str().map(String::from),
+    })
+}
+
+/// Reads a package of exactly `N` integers.
+fn integers<const N: usize>(object: &Object) -> Result<[u64; N], ACPI_STATUS> {
+    let elements = object.as_package().ok_or(AE_TYPE)?;
+
+    if elements.len() != N {
+        return Err(AE_TYPE);
+    }
+
+    let mut values = [0; N];
+
+    for (value, element) in values.iter_mut().zip(elements) {
+        *value = integer(element)?;
+    }
+
+    Ok(values)
+}
+
+/// Decodes a buffer holding a `Register()` descriptor.
+fn register(object: &Object) -> Result<GenericAddress, ACPI_STATUS> {
+    object.as_buffer().and_then(generic_register).ok_or(AE_TYPE)
+}
+
+/// Decodes an optional register of `_LPI`, which is left out by giving a zero address.
+fn optional_register(object: &Object) -> Option<GenericAddress> {
+    register(object)
+        .ok()
+        .filter(|register| register.address != 0)
+}
@@ -0,0 +1,438 @@
+//! Rust handlers for AML operation regions.
+//!
+//! An operation region handler services the reads and writes AML performs on the fields of
+//! an `OperationRegion` in a given address space. Handlers are installed for a device and
+//! apply to every region below it in the namespace; installing on [`Handle::root`] covers
+//! the whole namespace. A handler is removed again when its [`RegionHandlerRegistration`]
+//! is dropped.
+
+use core::ffi::c_void;
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+
+use crate::{namespace::Handle, to_result, AE_OK};
+
+/// An address space an operation region can live in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressSpace {
+    SystemMemory,
+    SystemIo,
+    PciConfig,
+    EmbeddedController,
+    SmBus,
+    Cmos,
+    PciBarTarget,
+    Ipmi,
+    GeneralPurposeIo,
+    GenericSerialBus,
+    PlatformCommunication,
+    PlatformRuntime,
+    FixedHardware,
+    /// An OEM defined address space, `0x80` to `0xFF`.
+    Oem(u8),
+}
+
+impl AddressSpace {
+    /// Returns the `ACPI_ADR_SPACE_*` id of the address space.
+    pub const fn id(self) -> ACPI_ADR_SPACE_TYPE {
+        match self {
+            Self::SystemMemory => 0,
+            Self::SystemIo => 1,
+            Self::PciConfig => 2,
+            Self::EmbeddedController => 3,
+            Self::SmBus => 4,
+            Self::Cmos => 5,
+            Self::PciBarTarget => 6,
+            Self::Ipmi => 7,
+            Self::GeneralPurposeIo => 8,
+            Self::GenericSerialBus => 9,
+            Self::PlatformCommunication => 10,
+            Self::PlatformRuntime => 11,
+            Self::FixedHardware => 0x7F,
+            Self::Oem(id) => id,
+        }
+    }
+
+    /// Looks up the address space with the given `ACPI_ADR_SPACE_*` id.
+    pub const fn from_id(id: ACPI_ADR_SPACE_TYPE) -> Option<Self> {
+        match id {
+            0 => Some(Self::SystemMemory),
+            1 => Some(Self::SystemIo),
+            2 => Some(Self::PciConfig),
+            3 => Some(Self::EmbeddedController),
+            4 => Some(Self::SmBus),
+            5 => Some(Self::Cmos),
+            6 => Some(Self::PciBarTarget),
+            7 => Some(Self::Ipmi),
+            8 => Some(Self::GeneralPurposeIo),
+            9 => Some(Self::GenericSerialBus),
+            10 => Some(Self::PlatformCommunication),
+            11 => Some(Self::PlatformRuntime),
+            0x7F => Some(Self::FixedHardware),
+            0x80..=0xFF => Some(Self::Oem(id)),
+            _ => None,
+        }
+    }
+}
+
+/// Whether AML reads from or writes to a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionAccess {
+    Read,
+    Write,
+}
+
+/// The `Connection()` resource active for a GenericSerialBus or GeneralPurposeIo field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionConnection<'a> {
+    /// The resource template, e.g. an `I2cSerialBusV2` or `GpioIo` descriptor.
+    pub resource: &'a [u8],
+    /// The length given by `AccessAs()`, for protocols that take one.
+    pub access_length: u8,
+}
+
+/// Data ACPICA passes to handlers of some address spaces through the handler context.
+///
+/// ACPICA writes to it directly, so it has to be the first field of the context.
+#[repr(C)]
+#[derive(Clone, Copy)]
+union SpaceInfo {
+    connection: ACPI_CONNECTION_INFO,
+    pcc: ACPI_PCC_INFO,
+    ffh: ACPI_FFH_INFO,
+}
+
+/// Information available when a region is first accessed.
+pub struct RegionActivation<'a> {
+    space: AddressSpace,
+    info: &'a SpaceInfo,
+}
+
+impl RegionActivation<'_> {
+    pub fn space(&self) -> AddressSpace {
+        self.space
+    }
+
+    /// Returns the subspace ID, length and internal buffer of a PCC region.
+    pub fn pcc(&self) -> Option<ACPI_PCC_INFO> {
+        (self.space == AddressSpace::PlatformCommunication).then(|| unsafe { self.info.pcc })
+    }
+
+    /// Returns the offset and length of a fixed hardware region.
+    pub fn ffh(&self) -> Option<ACPI_FFH_INFO> {
+        (self.space == AddressSpace::FixedHardware).then(|| unsafe { self.info.ffh })
+    }
+}
+
+/// A single access AML performs on a region.
+pub struct RegionRequest<'a> {
+    space: AddressSpace,
+    function: u32,
+    address: u64,
+    bit_width: u32,
+    value: *mut u64,
+    info: &'a SpaceInfo,
+}
+
+impl<'a> RegionRequest<'a> {
+    pub fn space(&self) -> AddressSpace {
+        self.space
+    }
+
+    pub fn access(&self) -> RegionAccess {
+        match self.function & ACPI_IO_MASK {
+            ACPI_WRITE => RegionAccess::Write,
+            _ => RegionAccess::Read,
+        }
+    }
+
+    /// The access attribute of the field (the upper 16 bits of the function code).
+    ///
+    /// For SMBus and GenericSerialBus regions this is the access protocol given by `AccessAs()`.
+    pub fn attribute(&self) -> u16 {
+        (self.function >> 16) as u16
+    }
+
+    /// The address being accessed, i.e. the region base plus the offset of the field.
+    ///
+    /// For GeneralPurposeIo regions this is the index of the first pin in the connection.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Width of the access in bits. For GeneralPurposeIo regions this is the number of pins.
+    pub fn bit_width(&self) -> u32 {
+        self.bit_width
+    }
+
+    /// The value AML writes.
+    pub fn value(&self) -> u64 {
+        unsafe { *self.value }
+    }
+
+    /// Sets the value returned to AML by a read.
+    pub fn set_value(&mut self, value: u64) {
+        unsafe { *self.value = value }
+    }
+
+    /// Returns the data buffer of address spaces that transfer buffers rather than integers
+    /// (SMBus, GenericSerialBus, IPMI, PCC and PRM).
+    ///
+    /// # Safety
+    ///
+    /// `length` must not exceed the size of the buffer ACPICA allocated for this address space
+    /// and access protocol.
+    pub unsafe fn buffer(&mut self, length: usize) -> &mut [u8] {
+        core::slice::from_raw_parts_mut(self.value as *mut u8, length)
+    }
+
+    /// Returns the active connection of GenericSerialBus and GeneralPurposeIo fields.
+    pub fn connection(&self) -> Option<RegionConnection<'a>> {
+        match self.space {
+            AddressSpace::GenericSerialBus | AddressSpace::GeneralPurposeIo => {
+                let connection = unsafe { self.info.connection };
+
+                if connection.Connection.is_null() {
+                    return None;
+                }
+
+                Some(RegionConnection {
+                    resource: unsafe {
+                        core::slice::from_raw_parts(
+                            connection.Connection,
+                            connection.Length as usize,
+                        )
+                    },
+                    access_length: connection.AccessLength,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Services accesses to operation regions of one address space.
+pub trait RegionHandler: Send + Sync + 'static {
+    /// State kept for each region between its activation and deactivation.
+    ///
+    /// ACPICA leaves the interpreter before calling the handler, so accesses to the same
+    /// region can run concurrently. Context that changes needs its own lock.
+    type Context: Send + Sync;
+
+    /// Called the first time AML accesses a region.
+    fn activate(&self, activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS>;
+
+    /// Called when a region goes away, e.g. because its table was unloaded.
+    fn deactivate(&self, _context: Self::Context) {}
+
+    /// Performs a single read or write.
+    fn access(
+        &self,
+        context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS>;
+}
+
+/// Adapts a closure into a [`RegionHandler`] without per-region state.
+pub struct FnRegionHandler<F>(pub F);
+
+impl<F> RegionHandler for FnRegionHandler<F>
+where
+    F: Fn(&mut RegionRequest) -> Result<(), ACPI_STATUS> + Send + Sync + 'static,
+{
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        (self.0)(request)
+    }
+}
+
+/// Object safe version of [`RegionHandler`], with the region context type erased.
+trait ErasedRegionHandler: Send + Sync {
+    fn activate(&self, activation: &RegionActivation) -> Result<*mut c_void, ACPI_STATUS>;
+    fn deactivate(&self, context: *mut c_void);
+    fn access(&self, context: *mut c_void, request: &mut RegionRequest) -> Result<(), ACPI_STATUS>;
+}
+
+impl<H: RegionHandler> ErasedRegionHandler for H {
+    fn activate(&self, activation: &RegionActivation) -> Result<*mut c_void, ACPI_STATUS> {
+        let context = RegionHandler::activate(self, activation)?;
+
+        Ok(Box::into_raw(Box::new(context)) as *mut c_void)
+    }
+
+    fn deactivate(&self, context: *mut c_void) {
+        let context = unsafe { Box::from_raw(context as *mut H::Context) };
+
+        RegionHandler::deactivate(self, *context)
+    }
+
+    fn access(&self, context: *mut c_void, request: &mut RegionRequest) -> Result<(), ACPI_STATUS> {
+        let context = unsafe { &*(context as *const H::Context) };
+
+        RegionHandler::access(self, context, request)
+    }
+}
+
+#[repr(C)]
+struct HandlerContext {
+    info: SpaceInfo,
+    space: AddressSpace,
+    handler: Box<dyn ErasedRegionHandler>,
+}
+
+/// An installed operation region handler. Dropping it removes the handler.
+pub struct RegionHandlerRegistration {
+    device: Handle,
+    space: AddressSpace,
+    context: *mut HandlerContext,
+}
+
+unsafe impl Send for RegionHandlerRegistration {}
+unsafe impl Sync for RegionHandlerRegistration {}
+
+impl RegionHandlerRegistration {
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    pub fn space(&self) -> AddressSpace {
+        self.space
+    }
+}
+
+impl Drop for RegionHandlerRegistration {
+    fn drop(&mut self) {
+        // Removing the handler deactivates all regions using it, so the context is unused afterwards
+        let status = unsafe {
+            AcpiRemoveAddressSpaceHandler(
+                self.device.as_raw(),
+                self.space.id(),
+                Some(region_handler),
+            )
+        };
+
+        if status == AE_OK {
+            drop(unsafe { Box::from_raw(self.context) });
+        }
+    }
+}
+
+/// Installs `handler` for the regions of `space` below `device`.
+///
+/// ACPICA runs the `_REG` methods of the affected regions before this returns.
+pub fn install_handler<H: RegionHandler>(
+    device: Handle,
+    space: AddressSpace,
+    handler: H,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    let context = Box::into_raw(Box::new(HandlerContext {
+        info: SpaceInfo {
+            connection: ACPI_CONNECTION_INFO {
+                Connection: core::ptr::null_mut(),
+                Length: 0,
+                AccessLength: 0,
+            },
+        },
+        space,
+        handler: Box::new(handler),
+    }));
+
+    let status = unsafe {
+        AcpiInstallAddressSpaceHandler(
+            device.as_raw(),
+            space.id(),
+            Some(region_handler),
+            Some(region_setup),
+            context as *mut c_void,
+        )
+    };
+
+    if let Err(status) = to_result(status) {
+        drop(unsafe { Box::from_raw(context) });
+        return Err(status);
+    }
+
+    Ok(RegionHandlerRegistration {
+        device,
+        space,
+        context,
+    })
+}
+
+/// Installs a closure as the handler for the regions of `space` below `device`.
+pub fn install_fn_handler<F>(
+    device: Handle,
+    space: AddressSpace,
+    handler: F,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS>
+where
+    F: Fn(&mut RegionRequest) -> Result<(), ACPI_STATUS> + Send + Sync + 'static,
+{
+    install_handler(device, space, FnRegionHandler(handler))
+}
+
+unsafe extern "C" fn region_setup(
+    _RegionHandle: ACPI_HANDLE,
+    Function: u32,
+    HandlerContext: *mut c_void,
+    RegionContext: *mut *mut c_void,
+) -> ACPI_STATUS {
+    let context = &*(HandlerContext as *const HandlerContext);
+
+    if Function == ACPI_REGION_DEACTIVATE {
+        if !(*RegionContext).is_null() {
+            context.handler.deactivate(*RegionContext);
+            *RegionContext = core::ptr::null_mut();
+        }
+
+        return AE_OK;
+    }
+
+    let activation = RegionActivation {
+        space: context.space,
+        info: &context.info,
+    };
+
+    match context.handler.activate(&activation) {
+        Ok(region_context) => {
+            *RegionContext = region_context;
+            AE_OK
+        }
+        Err(status) => status,
+    }
+}
+
+unsafe extern "C" fn region_handler(
+    Function: u32,
+    Address: ACPI_PHYSICAL_ADDRESS,
+    BitWidth: u32,
+    Value: *mut u64,
+    HandlerContext: *mut c_void,
+    RegionContext: *mut c_void,
+) -> ACPI_STATUS {
+    let context = &*(HandlerContext as *const HandlerContext);
+
+    let mut request = RegionRequest {
+        space: context.space,
+        function: Function,
+        address: Address,
+        bit_width: BitWidth,
+        value: Value,
+        info: &context.info,
+    };
+
+    match context.handler.access(RegionContext, &mut request) {
+        Ok(()) => AE_OK,
+        Err(status) => status,
+    }
+}
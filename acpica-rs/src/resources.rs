@@ -0,0 +1,427 @@
+//! Device resource settings, as returned by `_CRS`, and decoders for raw resource
+//! descriptors.
+
+use acpica_sys::*;
+use alloc::{string::String, vec::Vec};
+
+use crate::{buffer::AcpiBuffer, tables::GenericAddress};
+
+/// Size of the `Type` and `Length` fields every resource starts with.
+const RESOURCE_HEADER_SIZE: usize = 8;
+
+/// A range of I/O ports decoded by a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPortRange {
+    pub base: u16,
+    pub length: u8,
+}
+
+/// A range of physical memory decoded by a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRange {
+    pub base: u32,
+    pub length: u32,
+}
+
+/// A resource list converted by ACPICA from a resource template.
+pub struct Resources {
+    buffer: AcpiBuffer,
+}
+
+impl Resources {
+    pub(crate) fn new(buffer: AcpiBuffer) -> Self {
+        Self { buffer }
+    }
+
+    /// Iterates over the resources in the list, excluding the end tag.
+    pub fn iter(&self) -> impl Iterator<Item = &ACPI_RESOURCE> + '_ {
+        let start = self.buffer.pointer() as *const u8;
+        let end = self.buffer.len();
+        let mut offset = 0;
+
+        core::iter::from_fn(move || {
+            if offset + RESOURCE_HEADER_SIZE > end {
+                return None;
+            }
+
+            // ACPI_RESOURCE is packed, so any offset is suitably aligned
+            let resource = unsafe { &*(start.add(offset) as *const ACPI_RESOURCE) };
+            let length = resource.Length as usize;
+
+            if resource.Type == ACPI_RESOURCE_TYPE_END_TAG || length == 0 || offset + length > end {
+                return None;
+            }
+
+            offset += length;
+            Some(resource)
+        })
+    }
+
+    /// Returns the I/O port ranges of `IO` and `FixedIO` descriptors, in list order.
+    pub fn io_ports(&self) -> Vec<IoPortRange> {
+        self.iter()
+            .filter_map(|resource| unsafe {
+                match resource.Type {
+                    ACPI_RESOURCE_TYPE_IO => Some(IoPortRange {
+                        base: resource.Data.Io.Minimum,
+                        length: resource.Data.Io.AddressLength,
+                    }),
+                    ACPI_RESOURCE_TYPE_FIXED_IO => Some(IoPortRange {
+                        base: resource.Data.FixedIo.Address,
+                        length: resource.Data.FixedIo.AddressLength,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the memory ranges of `Memory32` and `FixedMemory32` descriptors, in list order.
+    pub fn memory_ranges(&self) -> Vec<MemoryRange> {
+        self.iter()
+            .filter_map(|resource| unsafe {
+                match resource.Type {
+                    ACPI_RESOURCE_TYPE_MEMORY32 => Some(MemoryRange {
+                        base: resource.Data.Memory32.Minimum,
+                        length: resource.Data.Memory32.AddressLength,
+                    }),
+                    ACPI_RESOURCE_TYPE_FIXED_MEMORY32 => Some(MemoryRange {
+                        base: resource.Data.FixedMemory32.Address,
+                        length: resource.Data.FixedMemory32.AddressLength,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the interrupts of `IRQ` and `Interrupt` descriptors, in list order.
+    pub fn interrupts(&self) -> Vec<u32> {
+        let mut interrupts = Vec::new();
+
+        for resource in self.iter() {
+            unsafe {
+                match resource.Type {
+                    ACPI_RESOURCE_TYPE_IRQ => {
+                        let count = resource.Data.Irq.InterruptCount as usize;
+                        let list =
+                            core::ptr::addr_of!(resource.Data.Irq.__bindgen_anon_1) as *const u8;
+
+                        for i in 0..count {
+                            interrupts.push(list.add(i).read() as u32);
+                        }
+                    }
+                    ACPI_RESOURCE_TYPE_EXTENDED_IRQ => {
+                        let count = resource.Data.ExtendedIrq.InterruptCount as usize;
+                        let list = core::ptr::addr_of!(resource.Data.ExtendedIrq.__bindgen_anon_1)
+                            as *const u32;
+
+                        for i in 0..count {
+                            interrupts.push(list.add(i).read_unaligned());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        interrupts
+    }
+}
+
+/// Offsets into a raw `I2cSerialBusV2` resource descriptor.
+const SERIAL_BUS_TYPE_OFFSET: usize = 5;
+const SERIAL_BUS_GENERAL_FLAGS_OFFSET: usize = 6;
+const SERIAL_BUS_TYPE_FLAGS_OFFSET: usize = 7;
+const SERIAL_BUS_TYPE_DATA_LENGTH_OFFSET: usize = 10;
+const SERIAL_BUS_TYPE_DATA_OFFSET: usize = 12;
+
+const SERIAL_BUS_DESCRIPTOR: u8 = 0x8E;
+const SERIAL_BUS_TYPE_I2C: u8 = 1;
+
+/// Length of the I2C specific data before the vendor data.
+const I2C_TYPE_DATA_LENGTH: usize = 6;
+
+/// An I2C connection, decoded from a raw `I2cSerialBusV2` resource descriptor as found in
+/// `Connection()` fields and `_CRS` templates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct I2cSerialBus {
+    pub slave_address: u16,
+    pub ten_bit_addressing: bool,
+    /// Bus speed in Hz.
+    pub connection_speed: u32,
+    /// Whether the device, rather than the controller, initiates transfers.
+    pub device_initiated: bool,
+    pub shared: bool,
+    /// Namespace path of the I2C controller the device is connected to.
+    pub controller: String,
+    pub vendor_data: Vec<u8>,
+}
+
+impl I2cSerialBus {
+    /// Decodes a raw resource descriptor, returning `None` if it is not a valid I2C
+    /// serial bus descriptor.
+    pub fn parse(descriptor: &[u8]) -> Option<Self> {
+        let end = 3 + read_u16(descriptor, 1)? as usize;
+        let descriptor = descriptor.get(..end)?;
+
+        if descriptor[0] != SERIAL_BUS_DESCRIPTOR
+            || *descriptor.get(SERIAL_BUS_TYPE_OFFSET)? != SERIAL_BUS_TYPE_I2C
+        {
+            return None;
+        }
+
+        let general_flags = *descriptor.get(SERIAL_BUS_GENERAL_FLAGS_OFFSET)?;
+        let type_flags = read_u16(descriptor, SERIAL_BUS_TYPE_FLAGS_OFFSET)?;
+        let type_data_length = read_u16(descriptor, SERIAL_BUS_TYPE_DATA_LENGTH_OFFSET)? as usize;
+        let type_data = descriptor
+            .get(SERIAL_BUS_TYPE_DATA_OFFSET..SERIAL_BUS_TYPE_DATA_OFFSET + type_data_length)?;
+
+        if type_data.len() < I2C_TYPE_DATA_LENGTH {
+            return None;
+        }
+
+        let source = &descriptor[SERIAL_BUS_TYPE_DATA_OFFSET + type_data_length..];
+        let source_length = source
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(source.len());
+
+        Some(Self {
+            slave_address: u16::from_le_bytes([type_data[4], type_data[5]]),
+            ten_bit_addressing: type_flags & 1 != 0,
+            connection_speed: u32::from_le_bytes([
+                type_data[0],
+                type_data[1],
+                type_data[2],
+                type_data[3],
+            ]),
+            device_initiated: general_flags & (1 << 0) != 0,
+            shared: general_flags & (1 << 2) != 0,
+            controller: String::from_utf8_lossy(&source[..source_length]).into_owned(),
+            vendor_data: type_data[I2C_TYPE_DATA_LENGTH..].to_vec(),
+        })
+    }
+}
+
+/// Offsets into a raw `GpioInt` or `GpioIo` resource descriptor.
+const GPIO_CONNECTION_TYPE_OFFSET: usize = 4;
+const GPIO_FLAGS_OFFSET: usize = 7;
+const GPIO_PIN_CONFIG_OFFSET: usize = 9;
+const GPIO_DRIVE_STRENGTH_OFFSET: usize = 10;
+const GPIO_DEBOUNCE_TIMEOUT_OFFSET: usize = 12;
+const GPIO_PIN_TABLE_OFFSET: usize = 14;
+const GPIO_RESOURCE_SOURCE_OFFSET: usize = 17;
+const GPIO_VENDOR_DATA_OFFSET: usize = 19;
+const GPIO_VENDOR_LENGTH_OFFSET: usize = 21;
+
+const GPIO_DESCRIPTOR: u8 = 0x8C;
+const GPIO_CONNECTION_INTERRUPT: u8 = 0;
+const GPIO_CONNECTION_IO: u8 = 1;
+
+/// Type of the small end tag descriptor.
+const SMALL_END_TAG: u8 = 0xF;
+
+/// How an interrupt is signalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Triggering {
+    Level,
+    Edge,
+}
+
+/// Which level or edge of an interrupt is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+    ActiveBoth,
+}
+
+/// How a GPIO pin may be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpioIoRestriction {
+    None,
+    InputOnly,
+    OutputOnly,
+    /// The pin configuration must be preserved when the connection is released.
+    Preserve,
+}
+
+/// The pull configuration of a GPIO pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpioPinConfig {
+    Default,
+    PullUp,
+    PullDown,
+    NoPull,
+    Vendor(u8),
+}
+
+/// What a GPIO connection is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpioConnectionKind {
+    Interrupt {
+        triggering: Triggering,
+        polarity: Polarity,
+        wake_capable: bool,
+    },
+    Io {
+        restriction: GpioIoRestriction,
+    },
+}
+
+/// A GPIO connection, decoded from a raw `GpioInt` or `GpioIo` resource descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpioConnection {
+    pub kind: GpioConnectionKind,
+    pub shared: bool,
+    pub pin_config: GpioPinConfig,
+    /// Output drive strength, in hundredths of milliamperes.
+    pub drive_strength: u16,
+    /// Debounce timeout, in hundredths of milliseconds.
+    pub debounce_timeout: u16,
+    /// Pin numbers, relative to the GPIO controller.
+    pub pins: Vec<u16>,
+    /// Namespace path of the GPIO controller.
+    pub controller: String,
+    pub vendor_data: Vec<u8>,
+}
+
+impl GpioConnection {
+    /// Decodes a raw resource descriptor, returning `None` if it is not a valid GPIO
+    /// connection descriptor.
+    pub fn parse(descriptor: &[u8]) -> Option<Self> {
+        let end = 3 + read_u16(descriptor, 1)? as usize;
+        let descriptor = descriptor.get(..end)?;
+
+        if descriptor[0] != GPIO_DESCRIPTOR {
+            return None;
+        }
+
+        let flags = read_u16(descriptor, GPIO_FLAGS_OFFSET)?;
+
+        let kind = match *descriptor.get(GPIO_CONNECTION_TYPE_OFFSET)? {
+            GPIO_CONNECTION_INTERRUPT => GpioConnectionKind::Interrupt {
+                triggering: match flags & 1 {
+                    0 => Triggering::Level,
+                    _ => Triggering::Edge,
+                },
+                polarity: match (flags >> 1) & 0x3 {
+                    0 => Polarity::ActiveHigh,
+                    1 => Polarity::ActiveLow,
+                    _ => Polarity::ActiveBoth,
+                },
+                wake_capable: flags & (1 << 4) != 0,
+            },
+            GPIO_CONNECTION_IO => GpioConnectionKind::Io {
+                restriction: match flags & 0x3 {
+                    0 => GpioIoRestriction::None,
+                    1 => GpioIoRestriction::InputOnly,
+                    2 => GpioIoRestriction::OutputOnly,
+                    _ => GpioIoRestriction::Preserve,
+                },
+            },
+            _ => return None,
+        };
+
+        let pin_config = match *descriptor.get(GPIO_PIN_CONFIG_OFFSET)? {
+            0 => GpioPinConfig::Default,
+            1 => GpioPinConfig::PullUp,
+            2 => GpioPinConfig::PullDown,
+            3 => GpioPinConfig::NoPull,
+            other => GpioPinConfig::Vendor(other),
+        };
+
+        // The pin table runs up to the resource source name
+        let pin_table = read_u16(descriptor, GPIO_PIN_TABLE_OFFSET)? as usize;
+        let source = read_u16(descriptor, GPIO_RESOURCE_SOURCE_OFFSET)? as usize;
+        let pins = descriptor
+            .get(pin_table..source)?
+            .chunks_exact(2)
+            .map(|pin| u16::from_le_bytes([pin[0], pin[1]]))
+            .collect();
+
+        let vendor_offset = read_u16(descriptor, GPIO_VENDOR_DATA_OFFSET)? as usize;
+        let vendor_length = read_u16(descriptor, GPIO_VENDOR_LENGTH_OFFSET)? as usize;
+        let vendor_data = match vendor_length {
+            0 => Vec::new(),
+            _ => descriptor
+                .get(vendor_offset..vendor_offset + vendor_length)?
+                .to_vec(),
+        };
+
+        let source = descriptor.get(source..)?;
+        let source_length = source
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(source.len());
+
+        Some(Self {
+            kind,
+            shared: flags & (1 << 3) != 0,
+            pin_config,
+            drive_strength: read_u16(descriptor, GPIO_DRIVE_STRENGTH_OFFSET)?,
+            debounce_timeout: read_u16(descriptor, GPIO_DEBOUNCE_TIMEOUT_OFFSET)?,
+            pins,
+            controller: String::from_utf8_lossy(&source[..source_length]).into_owned(),
+            vendor_data,
+        })
+    }
+}
+
+/// Offsets into a raw `Register()` resource descriptor.
+const REGISTER_SPACE_ID_OFFSET: usize = 3;
+const REGISTER_BIT_WIDTH_OFFSET: usize = 4;
+const REGISTER_BIT_OFFSET_OFFSET: usize = 5;
+const REGISTER_ACCESS_SIZE_OFFSET: usize = 6;
+const REGISTER_ADDRESS_OFFSET: usize = 7;
+
+const GENERIC_REGISTER_DESCRIPTOR: u8 = 0x82;
+
+/// Decodes a raw `Register()` descriptor, as found in the buffers of `_PCT`, `_CST` and
+/// `_LPI`. Returns `None` if the buffer doesn't start with one.
+pub fn generic_register(descriptor: &[u8]) -> Option<GenericAddress> {
+    if *descriptor.first()? != GENERIC_REGISTER_DESCRIPTOR {
+        return None;
+    }
+
+    let address = descriptor.get(REGISTER_ADDRESS_OFFSET..REGISTER_ADDRESS_OFFSET + 8)?;
+
+    Some(GenericAddress {
+        space_id: *descriptor.get(REGISTER_SPACE_ID_OFFSET)?,
+        bit_width: *descriptor.get(REGISTER_BIT_WIDTH_OFFSET)?,
+        bit_offset: *descriptor.get(REGISTER_BIT_OFFSET_OFFSET)?,
+        access_width: *descriptor.get(REGISTER_ACCESS_SIZE_OFFSET)?,
+        address: u64::from_le_bytes(address.try_into().ok()?),
+    })
+}
+
+/// Splits a raw resource template, e.g. the buffer returned by `_AEI`, into its descriptors.
+///
+/// Iteration stops at the end tag or at the first truncated descriptor.
+pub fn raw_descriptors(template: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut offset = 0;
+
+    core::iter::from_fn(move || {
+        let tag = *template.get(offset)?;
+
+        let length = match tag & 0x80 {
+            // Small descriptor, the length is in the tag
+            0 if (tag >> 3) & 0xF == SMALL_END_TAG => return None,
+            0 => 1 + (tag & 0x7) as usize,
+            _ => 3 + read_u16(template, offset + 1)? as usize,
+        };
+
+        let descriptor = template.get(offset..offset + length)?;
+        offset += length;
+
+        Some(descriptor)
+    })
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *bytes.get(offset)?,
+        *bytes.get(offset + 1)?,
+    ]))
+}
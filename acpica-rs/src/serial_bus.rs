@@ -0,0 +1,286 @@
+//! SMBus and GenericSerialBus operation regions.
+//!
+//! AML accesses SMBus and I2C devices through fields of `SMBus` and `GenericSerialBus`
+//! operation regions. Every access carries a protocol, chosen with `AccessAs()`, and
+//! exchanges a buffer that starts with a status byte and a length byte. The handlers here
+//! decode those into a [`SerialBusTransfer`] and leave the actual bus transaction to a
+//! [`SerialBus`] implementation provided by the OS.
+
+use acpica_sys::*;
+
+use crate::{
+    namespace::Handle,
+    region::{
+        self, AddressSpace, RegionAccess, RegionActivation, RegionHandler,
+        RegionHandlerRegistration, RegionRequest,
+    },
+    resources::I2cSerialBus,
+    AE_BAD_PARAMETER, AE_NOT_FOUND,
+};
+
+/// Status and length bytes in front of the data of every transfer buffer.
+const SERIAL_HEADER_SIZE: usize = 2;
+const SMBUS_DATA_SIZE: usize = 32;
+const GSBUS_MAX_DATA_SIZE: usize = 255;
+
+const STATUS_OFFSET: usize = 0;
+const LENGTH_OFFSET: usize = 1;
+
+/// Access protocols selected with `AccessAs()`, the `AML_FIELD_ATTRIB_*` values.
+const ATTRIB_QUICK: u16 = 0x02;
+const ATTRIB_SEND_RECEIVE: u16 = 0x04;
+const ATTRIB_BYTE: u16 = 0x06;
+const ATTRIB_WORD: u16 = 0x08;
+const ATTRIB_BLOCK: u16 = 0x0A;
+const ATTRIB_BYTES: u16 = 0x0B;
+const ATTRIB_PROCESS_CALL: u16 = 0x0C;
+const ATTRIB_BLOCK_PROCESS_CALL: u16 = 0x0D;
+const ATTRIB_RAW_BYTES: u16 = 0x0E;
+const ATTRIB_RAW_PROCESS_BYTES: u16 = 0x0F;
+
+/// How data is exchanged with a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialBusProtocol {
+    /// A single bit, carried by the read/write direction.
+    Quick,
+    /// A single byte, without a command.
+    SendReceive,
+    Byte,
+    Word,
+    /// A block of up to 32 (SMBus) or 255 bytes, with a count transferred on the bus.
+    Block,
+    /// A fixed number of bytes following the command.
+    Bytes(u8),
+    /// Writes a word and reads a word back.
+    ProcessCall,
+    /// Writes a block and reads a block back.
+    BlockProcessCall,
+    /// A fixed number of bytes, without a command.
+    RawBytes(u8),
+    /// Writes a fixed number of bytes and reads the same number back, without a command.
+    RawProcessBytes(u8),
+}
+
+impl SerialBusProtocol {
+    /// Decodes an access attribute, taking byte counts from the `AccessAs()` length.
+    pub fn from_attribute(attribute: u16, access_length: u8) -> Option<Self> {
+        match attribute {
+            ATTRIB_QUICK => Some(Self::Quick),
+            ATTRIB_SEND_RECEIVE => Some(Self::SendReceive),
+            ATTRIB_BYTE => Some(Self::Byte),
+            ATTRIB_WORD => Some(Self::Word),
+            ATTRIB_BLOCK => Some(Self::Block),
+            ATTRIB_BYTES => Some(Self::Bytes(access_length)),
+            ATTRIB_PROCESS_CALL => Some(Self::ProcessCall),
+            ATTRIB_BLOCK_PROCESS_CALL => Some(Self::BlockProcessCall),
+            ATTRIB_RAW_BYTES => Some(Self::RawBytes(access_length)),
+            ATTRIB_RAW_PROCESS_BYTES => Some(Self::RawProcessBytes(access_length)),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of data bytes the protocol transfers, or `None` if the count is
+    /// part of the transfer.
+    pub fn fixed_length(&self) -> Option<usize> {
+        match self {
+            Self::Quick => Some(0),
+            Self::SendReceive | Self::Byte => Some(1),
+            Self::Word | Self::ProcessCall => Some(2),
+            Self::Bytes(length) | Self::RawBytes(length) | Self::RawProcessBytes(length) => {
+                Some(*length as usize)
+            }
+            Self::Block | Self::BlockProcessCall => None,
+        }
+    }
+
+    /// Whether the protocol writes data and reads a reply in one transfer.
+    pub fn is_process_call(&self) -> bool {
+        matches!(
+            self,
+            Self::ProcessCall | Self::BlockProcessCall | Self::RawProcessBytes(_)
+        )
+    }
+
+    /// Size of the buffer ACPICA allocates for a GenericSerialBus access with this protocol.
+    fn gsbus_buffer_size(&self) -> usize {
+        let data_size = match self {
+            Self::Quick => 0,
+            Self::SendReceive | Self::Byte => 1,
+            Self::Word | Self::ProcessCall => 2,
+            _ => GSBUS_MAX_DATA_SIZE,
+        };
+
+        SERIAL_HEADER_SIZE + data_size
+    }
+}
+
+/// The device a transfer is addressed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerialBusTarget {
+    /// A device on an SMBus host controller, by its 7-bit address.
+    SmBus(u8),
+    /// A device on an I2C bus, as described by the `Connection()` of the field.
+    I2c(I2cSerialBus),
+}
+
+/// Why a transfer failed, reported to AML in the status byte of the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialBusError {
+    UnknownFailure,
+    AddressNotAcknowledged,
+    DeviceError,
+    CommandAccessDenied,
+    UnknownError,
+    AccessDenied,
+    Timeout,
+    UnsupportedProtocol,
+    Busy,
+    PecError,
+}
+
+impl SerialBusError {
+    /// The SMBus status code AML sees.
+    pub fn status(self) -> u8 {
+        match self {
+            Self::UnknownFailure => 0x07,
+            Self::AddressNotAcknowledged => 0x10,
+            Self::DeviceError => 0x11,
+            Self::CommandAccessDenied => 0x12,
+            Self::UnknownError => 0x13,
+            Self::AccessDenied => 0x17,
+            Self::Timeout => 0x18,
+            Self::UnsupportedProtocol => 0x19,
+            Self::Busy => 0x1A,
+            Self::PecError => 0x1F,
+        }
+    }
+}
+
+/// A single transfer requested by AML.
+pub struct SerialBusTransfer<'a> {
+    pub target: SerialBusTarget,
+    pub access: RegionAccess,
+    pub protocol: SerialBusProtocol,
+    /// The command byte, i.e. the offset of the field. Unused by the raw and send/receive
+    /// protocols.
+    pub command: u8,
+    /// For writes and process calls, the bytes to send. For reads and process calls, where
+    /// the bytes received go.
+    pub data: &'a mut [u8],
+    /// Number of valid bytes in `data`. Set by AML for writes, and by the bus for replies.
+    pub length: usize,
+}
+
+/// Performs transfers on behalf of SMBus and GenericSerialBus regions.
+pub trait SerialBus: Send + Sync + 'static {
+    fn transfer(&self, transfer: &mut SerialBusTransfer) -> Result<(), SerialBusError>;
+}
+
+/// Installs `bus` as the handler for `SMBus` regions below `device`.
+///
+/// The upper byte of an SMBus region address is the device address, the lower byte is the
+/// command.
+pub fn install_smbus_handler<B: SerialBus>(
+    device: Handle,
+    bus: B,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    region::install_handler(device, AddressSpace::SmBus, SerialBusRegionHandler { bus })
+}
+
+/// Installs `bus` as the handler for `GenericSerialBus` regions below `device`.
+///
+/// A single handler on [`Handle::root`] can serve every controller, as each transfer names
+/// its controller in [`I2cSerialBus::controller`].
+pub fn install_gsbus_handler<B: SerialBus>(
+    device: Handle,
+    bus: B,
+) -> Result<RegionHandlerRegistration, ACPI_STATUS> {
+    region::install_handler(
+        device,
+        AddressSpace::GenericSerialBus,
+        SerialBusRegionHandler { bus },
+    )
+}
+
+struct SerialBusRegionHandler<B> {
+    bus: B,
+}
+
+impl<B: SerialBus> RegionHandler for SerialBusRegionHandler<B> {
+    type Context = ();
+
+    fn activate(&self, _activation: &RegionActivation) -> Result<Self::Context, ACPI_STATUS> {
+        Ok(())
+    }
+
+    fn access(
+        &self,
+        _context: &Self::Context,
+        request: &mut RegionRequest,
+    ) -> Result<(), ACPI_STATUS> {
+        let (target, protocol, buffer_size, max_data) = match request.space() {
+            AddressSpace::SmBus => {
+                let protocol = SerialBusProtocol::from_attribute(request.attribute(), 0)
+                    .ok_or(AE_BAD_PARAMETER)?;
+                let target = SerialBusTarget::SmBus((request.address() >> 8) as u8 & 0x7F);
+
+                (
+                    target,
+                    protocol,
+                    SERIAL_HEADER_SIZE + SMBUS_DATA_SIZE,
+                    SMBUS_DATA_SIZE,
+                )
+            }
+            AddressSpace::GenericSerialBus => {
+                let connection = request.connection().ok_or(AE_NOT_FOUND)?;
+                let connection_target =
+                    I2cSerialBus::parse(connection.resource).ok_or(AE_BAD_PARAMETER)?;
+                let protocol = SerialBusProtocol::from_attribute(
+                    request.attribute(),
+                    connection.access_length,
+                )
+                .ok_or(AE_BAD_PARAMETER)?;
+                let buffer_size = protocol.gsbus_buffer_size();
+
+                (
+                    SerialBusTarget::I2c(connection_target),
+                    protocol,
+                    buffer_size,
+                    buffer_size - SERIAL_HEADER_SIZE,
+                )
+            }
+            _ => return Err(AE_BAD_PARAMETER),
+        };
+
+        let access = request.access();
+        let command = request.address() as u8;
+        let buffer = unsafe { request.buffer(buffer_size) };
+
+        let length = match (access, protocol.fixed_length()) {
+            (_, Some(length)) => length,
+            (RegionAccess::Write, None) => buffer[LENGTH_OFFSET] as usize,
+            (RegionAccess::Read, None) => max_data,
+        };
+
+        let (header, data) = buffer.split_at_mut(SERIAL_HEADER_SIZE);
+
+        let mut transfer = SerialBusTransfer {
+            target,
+            access,
+            protocol,
+            command,
+            length: length.min(max_data),
+            data,
+        };
+
+        // Failures are reported to AML through the status byte, not as an exception
+        header[STATUS_OFFSET] = match self.bus.transfer(&mut transfer) {
+            Ok(()) => 0,
+            Err(error) => error.status(),
+        };
+
+        header[LENGTH_OFFSET] = transfer.length.min(max_data) as u8;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+//! Typed access to ACPI tables.
+//!
+//! Tables are looked up through `AcpiGetTable` and released again with `AcpiPutTable` when
+//! the returned [`Table`] is dropped. The parsers in the submodules copy everything they need
+//! out of the table, so their results can outlive the table reference.
+
+pub mod bgrt;
+pub mod cedt;
+pub mod dynamic;
+pub mod ecdt;
+pub mod events;
+pub mod fpdt;
+pub mod nfit;
+pub mod pcct;
+pub mod prmt;
+pub mod spmi;
+pub mod tpm2;
+pub mod waet;
+
+use core::mem::{size_of, MaybeUninit};
+
+use acpica_sys::*;
+
+use crate::{to_result, AE_INVALID_TABLE_LENGTH};
+
+/// A reference to an ACPI table owned by ACPICA.
+pub struct Table {
+    header: *mut ACPI_TABLE_HEADER,
+}
+
+impl Table {
+    /// Looks up a table by its signature.
+    ///
+    /// * `signature` - The four character table signature, e.g. `b"BGRT"`.
+    /// * `instance` - Which instance of the table to return, starting at 1.
+    pub fn get(signature: &[u8; 4], instance: u32) -> Result<Self, ACPI_STATUS> {
+        let mut signature = [signature[0], signature[1], signature[2], signature[3], 0];
+        let mut header = core::ptr::null_mut();
+
+        to_result(unsafe {
+            AcpiGetTable(signature.as_mut_ptr() as ACPI_STRING, instance, &mut header)
+        })?;
+
+        Ok(Self { header })
+    }
+
+    /// Returns a copy of the common table header.
+    pub fn header(&self) -> ACPI_TABLE_HEADER {
+        unsafe { core::ptr::read_unaligned(self.header) }
+    }
+
+    /// Returns the whole table, including the header.
+    pub fn bytes(&self) -> &[u8] {
+        let length = self.header().Length as usize;
+
+        unsafe { core::slice::from_raw_parts(self.header as *const u8, length) }
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        unsafe { AcpiPutTable(self.header) }
+    }
+}
+
+/// A register location described by an ACPI Generic Address Structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenericAddress {
+    pub space_id: u8,
+    pub bit_width: u8,
+    pub bit_offset: u8,
+    pub access_width: u8,
+    pub address: u64,
+}
+
+impl GenericAddress {
+    /// Reads the register through `AcpiRead`.
+    pub fn read(&self) -> Result<u64, ACPI_STATUS> {
+        let mut gas = ACPI_GENERIC_ADDRESS::from(*self);
+        let mut value = 0;
+
+        to_result(unsafe { AcpiRead(&mut value, &mut gas) })?;
+
+        Ok(value)
+    }
+
+    /// Writes the register through `AcpiWrite`.
+    pub fn write(&self, value: u64) -> Result<(), ACPI_STATUS> {
+        let mut gas = ACPI_GENERIC_ADDRESS::from(*self);
+
+        to_result(unsafe { AcpiWrite(value, &mut gas) })
+    }
+}
+
+impl From<ACPI_GENERIC_ADDRESS> for GenericAddress {
+    fn from(gas: ACPI_GENERIC_ADDRESS) -> Self {
+        Self {
+            space_id: gas.SpaceId,
+            bit_width: gas.BitWidth,
+            bit_offset: gas.BitOffset,
+            access_width: gas.AccessWidth,
+            address: gas.Address,
+        }
+    }
+}
+
+impl From<GenericAddress> for ACPI_GENERIC_ADDRESS {
+    fn from(gas: GenericAddress) -> Self {
+        Self {
+            SpaceId: gas.space_id,
+            BitWidth: gas.bit_width,
+            BitOffset: gas.bit_offset,
+            AccessWidth: gas.access_width,
+            Address: gas.address,
+        }
+    }
+}
+
+/// Reads a `T` from `bytes` at `offset`, if it fits.
+pub(crate) fn read<T: Copy>(bytes: &[u8], offset: usize) -> Option<T> {
+    if offset.checked_add(size_of::<T>())? > bytes.len() {
+        return None;
+    }
+
+    Some(unsafe { core::ptr::read_unaligned(bytes.as_ptr().add(offset) as *const T) })
+}
+
+/// Reads the fixed part of a table, failing with `AE_INVALID_TABLE_LENGTH` if it is truncated.
+pub(crate) fn read_fixed<T: Copy>(bytes: &[u8]) -> Result<T, ACPI_STATUS> {
+    read(bytes, 0).ok_or(AE_INVALID_TABLE_LENGTH)
+}
+
+/// Reads a `T` from the start of `bytes`, zero filling whatever is missing.
+///
+/// Used for subtables that grew new trailing fields in later revisions. `T` must be a plain
+/// data structure for which all zeroes is a valid value.
+pub(crate) fn read_prefix<T: Copy>(bytes: &[u8]) -> T {
+    let mut value = MaybeUninit::<T>::zeroed();
+    let count = bytes.len().min(size_of::<T>());
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(bytes.as_ptr(), value.as_mut_ptr() as *mut u8, count);
+        value.assume_init()
+    }
+}
+
+/// Iterates over the subtables that follow the fixed part of a table.
+///
+/// * `offset` - Where the first subtable starts.
+/// * `length` - Extracts the length of a subtable, including its header, from its header.
+///
+/// Iteration stops at the first subtable whose length is invalid.
+pub(crate) fn subtables<H: Copy>(
+    bytes: &[u8],
+    offset: usize,
+    length: fn(&H) -> usize,
+) -> impl Iterator<Item = (H, &[u8])> {
+    let mut offset = offset;
+
+    core::iter::from_fn(move || {
+        let header: H = read(bytes, offset)?;
+        let subtable_length = length(&header);
+
+        if subtable_length < size_of::<H>() || offset + subtable_length > bytes.len() {
+            return None;
+        }
+
+        let data = &bytes[offset..offset + subtable_length];
+        offset += subtable_length;
+
+        Some((header, data))
+    })
+}
@@ -0,0 +1,69 @@
+//! BGRT - Boot Graphics Resource Table.
+
+use acpica_sys::*;
+
+use super::{read_fixed, Table};
+
+/// Rotation applied to the boot image, relative to the native display orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BgrtOrientation {
+    None,
+    Degrees90,
+    Degrees180,
+    Degrees270,
+}
+
+/// Format of the boot image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BgrtImageType {
+    Bitmap,
+    Reserved(u8),
+}
+
+/// The boot logo left on screen by the firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bgrt {
+    pub version: u16,
+    /// Whether the image is currently displayed on screen.
+    pub displayed: bool,
+    pub orientation: BgrtOrientation,
+    pub image_type: BgrtImageType,
+    /// Physical address of the image in memory.
+    pub image_address: u64,
+    pub image_offset_x: u32,
+    pub image_offset_y: u32,
+}
+
+impl Bgrt {
+    /// Looks up and parses the BGRT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"BGRT", 1)?.bytes())
+    }
+
+    /// Parses a BGRT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: ACPI_TABLE_BGRT = read_fixed(bytes)?;
+
+        let orientation = match (table.Status & ACPI_BGRT_ORIENTATION_OFFSET as u8) >> 1 {
+            0 => BgrtOrientation::None,
+            1 => BgrtOrientation::Degrees90,
+            2 => BgrtOrientation::Degrees180,
+            _ => BgrtOrientation::Degrees270,
+        };
+
+        let image_type = match table.ImageType {
+            0 => BgrtImageType::Bitmap,
+            other => BgrtImageType::Reserved(other),
+        };
+
+        Ok(Self {
+            version: table.Version,
+            displayed: table.Status & ACPI_BGRT_DISPLAYED as u8 != 0,
+            orientation,
+            image_type,
+            image_address: table.ImageAddress,
+            image_offset_x: table.ImageOffsetX,
+            image_offset_y: table.ImageOffsetY,
+        })
+    }
+}
@@ -0,0 +1,210 @@
+//! CEDT - CXL Early Discovery Table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use super::{read, read_fixed, subtables, Table};
+
+const ACPI_CEDT_TYPE_CHBS: u8 = 0;
+const ACPI_CEDT_TYPE_CFMWS: u8 = 1;
+
+/// The fixed part of `ACPI_CEDT_CFMWS`. bindgen doesn't derive `Copy` for the original
+/// because it ends in the flexible target array.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct CfmwsFixed {
+    header: ACPI_CEDT_HEADER,
+    reserved1: u32,
+    base_hpa: u64,
+    window_size: u64,
+    interleave_ways: u8,
+    interleave_arithmetic: u8,
+    reserved2: u16,
+    granularity: u32,
+    restrictions: u16,
+    qtg_id: u16,
+}
+
+const _: () = assert!(size_of::<CfmwsFixed>() == size_of::<ACPI_CEDT_CFMWS>());
+
+/// The CXL specification version a host bridge implements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CxlVersion {
+    Cxl11,
+    Cxl20,
+    Reserved(u32),
+}
+
+/// A CXL host bridge and the location of its component registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CxlHostBridge {
+    /// Matches the `_UID` of the host bridge device in the namespace.
+    pub uid: u32,
+    pub version: CxlVersion,
+    /// Base of the RCRB (CXL 1.1) or of the CHBCR (CXL 2.0).
+    pub base: u64,
+    pub length: u64,
+}
+
+/// How a host physical address selects an interleave target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CxlInterleaveArithmetic {
+    Modulo,
+    Xor,
+    Reserved(u8),
+}
+
+/// The kinds of memory a fixed memory window may be used for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CxlWindowRestrictions {
+    pub device_coherent: bool,
+    pub host_only_coherent: bool,
+    pub volatile: bool,
+    pub persistent: bool,
+    pub fixed_device_configuration: bool,
+}
+
+/// A CXL fixed memory window: a host physical address range interleaved across host bridges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CxlMemoryWindow {
+    pub base: u64,
+    pub size: u64,
+    /// Number of interleave targets, decoded from the encoded interleave ways.
+    pub interleave_ways: u32,
+    pub interleave_arithmetic: CxlInterleaveArithmetic,
+    /// Host bridge interleave granularity in bytes.
+    pub granularity: u64,
+    pub restrictions: CxlWindowRestrictions,
+    /// QoS throttling group this window belongs to.
+    pub qtg_id: u16,
+    /// `_UID`s of the target host bridges, in interleave order.
+    pub targets: Vec<u32>,
+}
+
+/// A memory window together with the host bridges it is interleaved across.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedCxlWindow<'a> {
+    pub window: &'a CxlMemoryWindow,
+    /// The host bridge for each interleave target, or `None` if no CHBS describes it.
+    pub host_bridges: Vec<Option<&'a CxlHostBridge>>,
+}
+
+/// The CXL host bridges and memory windows described by the CEDT.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cedt {
+    pub host_bridges: Vec<CxlHostBridge>,
+    pub memory_windows: Vec<CxlMemoryWindow>,
+}
+
+impl Cedt {
+    /// Looks up and parses the CEDT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"CEDT", 1)?.bytes())
+    }
+
+    /// Parses a CEDT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let _: ACPI_TABLE_CEDT = read_fixed(bytes)?;
+        let mut cedt = Self::default();
+
+        let entries = subtables(
+            bytes,
+            size_of::<ACPI_TABLE_CEDT>(),
+            |header: &ACPI_CEDT_HEADER| header.Length as usize,
+        );
+
+        for (header, data) in entries {
+            match header.Type {
+                ACPI_CEDT_TYPE_CHBS => {
+                    let Some(chbs) = read::<ACPI_CEDT_CHBS>(data, 0) else {
+                        continue;
+                    };
+
+                    let version = match chbs.CxlVersion {
+                        ACPI_CEDT_CHBS_VERSION_CXL11 => CxlVersion::Cxl11,
+                        ACPI_CEDT_CHBS_VERSION_CXL20 => CxlVersion::Cxl20,
+                        other => CxlVersion::Reserved(other),
+                    };
+
+                    cedt.host_bridges.push(CxlHostBridge {
+                        uid: chbs.Uid,
+                        version,
+                        base: chbs.Base,
+                        length: chbs.Length,
+                    });
+                }
+                ACPI_CEDT_TYPE_CFMWS => {
+                    let Some(cfmws) = read::<CfmwsFixed>(data, 0) else {
+                        continue;
+                    };
+
+                    let interleave_ways = decode_interleave_ways(cfmws.interleave_ways);
+                    let targets = (0..interleave_ways as usize)
+                        .map_while(|i| read::<u32>(data, size_of::<ACPI_CEDT_CFMWS>() + i * 4))
+                        .collect();
+
+                    let interleave_arithmetic = match cfmws.interleave_arithmetic as u32 {
+                        ACPI_CEDT_CFMWS_ARITHMETIC_MODULO => CxlInterleaveArithmetic::Modulo,
+                        ACPI_CEDT_CFMWS_ARITHMETIC_XOR => CxlInterleaveArithmetic::Xor,
+                        _ => CxlInterleaveArithmetic::Reserved(cfmws.interleave_arithmetic),
+                    };
+
+                    let restrictions = cfmws.restrictions as u32;
+
+                    cedt.memory_windows.push(CxlMemoryWindow {
+                        base: cfmws.base_hpa,
+                        size: cfmws.window_size,
+                        interleave_ways,
+                        interleave_arithmetic,
+                        granularity: 256u64 << cfmws.granularity.min(16),
+                        restrictions: CxlWindowRestrictions {
+                            device_coherent: restrictions & ACPI_CEDT_CFMWS_RESTRICT_TYPE2 != 0,
+                            host_only_coherent: restrictions & ACPI_CEDT_CFMWS_RESTRICT_TYPE3 != 0,
+                            volatile: restrictions & ACPI_CEDT_CFMWS_RESTRICT_VOLATILE != 0,
+                            persistent: restrictions & ACPI_CEDT_CFMWS_RESTRICT_PMEM != 0,
+                            fixed_device_configuration: restrictions
+                                & ACPI_CEDT_CFMWS_RESTRICT_FIXED
+                                != 0,
+                        },
+                        qtg_id: cfmws.qtg_id,
+                        targets,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(cedt)
+    }
+
+    /// Looks up the host bridge with the given `_UID`.
+    pub fn host_bridge(&self, uid: u32) -> Option<&CxlHostBridge> {
+        self.host_bridges.iter().find(|bridge| bridge.uid == uid)
+    }
+
+    /// Maps each memory window to the host bridges it is interleaved across.
+    pub fn resolve(&self) -> Vec<ResolvedCxlWindow<'_>> {
+        self.memory_windows
+            .iter()
+            .map(|window| ResolvedCxlWindow {
+                window,
+                host_bridges: window
+                    .targets
+                    .iter()
+                    .map(|&uid| self.host_bridge(uid))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Decodes the encoded number of interleave ways (ENIW) into a target count.
+fn decode_interleave_ways(encoded: u8) -> u32 {
+    match encoded {
+        0..=4 => 1 << encoded,
+        8..=10 => 3 << (encoded - 8),
+        _ => 0,
+    }
+}
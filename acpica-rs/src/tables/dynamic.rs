@@ -0,0 +1,133 @@
+//! Loading and unloading definition blocks at runtime.
+//!
+//! Secondary definition blocks can be added to the namespace after it was initialized, e.g.
+//! SSDT overlays that fix broken device descriptions. ACPICA does not copy a table passed to
+//! it and keeps referring to it even after it was unloaded, so the bytes of a table handed
+//! over here are never freed.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    namespace::Handle, to_result, AE_BAD_CHECKSUM, AE_BAD_SIGNATURE, AE_INVALID_TABLE_LENGTH,
+};
+
+/// A secondary definition block checked to be well-formed, ready to be handed to ACPICA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionBlock {
+    bytes: Box<[u8]>,
+}
+
+impl DefinitionBlock {
+    /// Checks that `bytes` hold a complete SSDT or `OEMx` table with a valid checksum.
+    ///
+    /// Fails with `AE_BAD_SIGNATURE` for other tables, `AE_INVALID_TABLE_LENGTH` if the
+    /// length in the header does not match and `AE_BAD_CHECKSUM` if the bytes don't add up
+    /// to zero.
+    pub fn new(bytes: Vec<u8>) -> Result<Self, ACPI_STATUS> {
+        if bytes.len() < size_of::<ACPI_TABLE_HEADER>() {
+            return Err(AE_INVALID_TABLE_LENGTH);
+        }
+
+        if &bytes[..4] != b"SSDT" && &bytes[..3] != b"OEM" {
+            return Err(AE_BAD_SIGNATURE);
+        }
+
+        let block = Self {
+            bytes: bytes.into_boxed_slice(),
+        };
+
+        if block.header().Length as usize != block.bytes.len() {
+            return Err(AE_INVALID_TABLE_LENGTH);
+        }
+
+        let checksum = block
+            .bytes
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum != 0 {
+            return Err(AE_BAD_CHECKSUM);
+        }
+
+        Ok(block)
+    }
+
+    /// Returns a copy of the table header.
+    pub fn header(&self) -> ACPI_TABLE_HEADER {
+        unsafe { core::ptr::read_unaligned(self.bytes.as_ptr() as *const ACPI_TABLE_HEADER) }
+    }
+
+    /// Returns the whole table, including the header.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Loads the table into the namespace, which runs its definition block.
+    ///
+    /// The table stays loaded until the returned [`LoadedTable`] is dropped.
+    pub fn load(self) -> Result<LoadedTable, ACPI_STATUS> {
+        let mut index = 0;
+
+        to_result(unsafe { AcpiLoadTable(self.leak(), &mut index) })?;
+
+        Ok(LoadedTable { index })
+    }
+
+    /// Adds the table to those [`AcpiLoadTables`](acpica_sys::AcpiLoadTables) loads.
+    ///
+    /// Only possible after the tables were initialized and before they are loaded. Installed
+    /// tables can't be removed again.
+    pub fn install(self) -> Result<(), ACPI_STATUS> {
+        to_result(unsafe { AcpiInstallTable(self.leak()) })
+    }
+
+    fn leak(self) -> *mut ACPI_TABLE_HEADER {
+        Box::leak(self.bytes).as_mut_ptr() as *mut ACPI_TABLE_HEADER
+    }
+}
+
+/// A table loaded with [`DefinitionBlock::load`]. Dropping it unloads the table, deleting
+/// the namespace objects it created.
+#[derive(Debug)]
+pub struct LoadedTable {
+    index: u32,
+}
+
+impl LoadedTable {
+    /// The index of the table in ACPICA's table list.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Unloads the table, returning why if that fails.
+    pub fn unload(self) -> Result<(), ACPI_STATUS> {
+        let index = self.index;
+        core::mem::forget(self);
+
+        to_result(unsafe { AcpiUnloadTable(index) })
+    }
+
+    /// Keeps the table loaded for good.
+    pub fn keep(self) -> u32 {
+        let index = self.index;
+        core::mem::forget(self);
+
+        index
+    }
+}
+
+impl Drop for LoadedTable {
+    fn drop(&mut self) {
+        unsafe { AcpiUnloadTable(self.index) };
+    }
+}
+
+/// Unloads the SSDT or `OEMx` table that created `object`, deleting all namespace objects
+/// it created.
+///
+/// Fails with `AE_TYPE` for objects created by the DSDT, which can't be unloaded.
+pub fn unload_parent_table(object: Handle) -> Result<(), ACPI_STATUS> {
+    to_result(unsafe { AcpiUnloadParentTable(object.as_raw()) })
+}
@@ -0,0 +1,62 @@
+//! ECDT - Embedded Controller Boot Resources Table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::string::String;
+
+use super::{read_fixed, GenericAddress, Table};
+
+/// The fixed part of `ACPI_TABLE_ECDT`. bindgen doesn't derive `Copy` for the original
+/// because it ends in the flexible `Id` array.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct EcdtFixed {
+    header: ACPI_TABLE_HEADER,
+    control: ACPI_GENERIC_ADDRESS,
+    data: ACPI_GENERIC_ADDRESS,
+    uid: u32,
+    gpe: u8,
+}
+
+const _: () = assert!(size_of::<EcdtFixed>() == size_of::<ACPI_TABLE_ECDT>());
+
+/// Describes the embedded controller early, before the namespace is loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ecdt {
+    /// The EC command/status register.
+    pub control: GenericAddress,
+    /// The EC data register.
+    pub data: GenericAddress,
+    /// Matches the `_UID` of the EC device.
+    pub uid: u32,
+    /// The GPE the EC signals SCIs on.
+    pub gpe: u8,
+    /// Absolute namespace path of the EC device, e.g. `\_SB.PCI0.LPCB.EC0`.
+    pub path: String,
+}
+
+impl Ecdt {
+    /// Looks up and parses the ECDT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"ECDT", 1)?.bytes())
+    }
+
+    /// Parses an ECDT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: EcdtFixed = read_fixed(bytes)?;
+
+        let id = bytes
+            .get(size_of::<ACPI_TABLE_ECDT>()..)
+            .unwrap_or_default();
+        let length = id.iter().position(|&byte| byte == 0).unwrap_or(id.len());
+
+        Ok(Self {
+            control: table.control.into(),
+            data: table.data.into(),
+            uid: table.uid,
+            gpe: table.gpe,
+            path: String::from_utf8_lossy(&id[..length]).into_owned(),
+        })
+    }
+}
@@ -0,0 +1,114 @@
+//! Notifications about tables being installed, loaded and unloaded.
+//!
+//! Besides the tables loaded at boot, AML can add definition blocks at any time through the
+//! `Load` and `LoadTable` operators, e.g. the SSDTs describing hotplugged processors. The OS
+//! has to rescan the namespace when that happens to find the new devices.
+
+use core::ffi::c_void;
+
+use acpica_sys::*;
+use alloc::boxed::Box;
+
+use crate::{to_result, AE_OK};
+
+/// What happened to a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableEventType {
+    /// The definition block of the table was loaded into the namespace.
+    Load,
+    /// The namespace objects created by the table were deleted.
+    Unload,
+    /// The table was added to the table list.
+    Install,
+    /// The table was removed from the table list. Current ACPICA never reports this.
+    Uninstall,
+}
+
+impl TableEventType {
+    fn from_raw(event: u32) -> Option<Self> {
+        match event {
+            ACPI_TABLE_EVENT_LOAD => Some(Self::Load),
+            ACPI_TABLE_EVENT_UNLOAD => Some(Self::Unload),
+            ACPI_TABLE_EVENT_INSTALL => Some(Self::Install),
+            ACPI_TABLE_EVENT_UNINSTALL => Some(Self::Uninstall),
+            _ => None,
+        }
+    }
+}
+
+/// An event reported to a table handler.
+#[derive(Debug, Clone, Copy)]
+pub struct TableEvent {
+    pub event_type: TableEventType,
+    /// A copy of the header of the table.
+    pub header: ACPI_TABLE_HEADER,
+}
+
+impl TableEvent {
+    /// The four character signature of the table, e.g. `b"SSDT"`.
+    pub fn signature(&self) -> [u8; 4] {
+        self.header.Signature.map(|character| character as u8)
+    }
+}
+
+/// Installs `handler` to be called for every table event. Only one handler can be installed
+/// at a time.
+///
+/// Events caused by AML arrive while the interpreter runs the `Load` operator, so rescanning
+/// the namespace has to be deferred to another thread.
+pub fn install_table_handler<F>(handler: F) -> Result<TableHandlerRegistration, ACPI_STATUS>
+where
+    F: Fn(TableEvent) + Send + Sync + 'static,
+{
+    let context = Box::into_raw(Box::new(TableHandlerContext {
+        handler: Box::new(handler),
+    }));
+
+    let status = unsafe { AcpiInstallTableHandler(Some(table_handler), context as *mut c_void) };
+
+    if let Err(status) = to_result(status) {
+        drop(unsafe { Box::from_raw(context) });
+        return Err(status);
+    }
+
+    Ok(TableHandlerRegistration { context })
+}
+
+struct TableHandlerContext {
+    handler: Box<dyn Fn(TableEvent) + Send + Sync>,
+}
+
+/// An installed table handler. Dropping it removes the handler.
+pub struct TableHandlerRegistration {
+    context: *mut TableHandlerContext,
+}
+
+unsafe impl Send for TableHandlerRegistration {}
+unsafe impl Sync for TableHandlerRegistration {}
+
+impl Drop for TableHandlerRegistration {
+    fn drop(&mut self) {
+        let status = unsafe { AcpiRemoveTableHandler(Some(table_handler)) };
+
+        if status == AE_OK {
+            drop(unsafe { Box::from_raw(self.context) });
+        }
+    }
+}
+
+unsafe extern "C" fn table_handler(Event: u32, Table: *mut c_void, Context: *mut c_void) -> u32 {
+    let context = &*(Context as *const TableHandlerContext);
+
+    if Table.is_null() {
+        return AE_OK;
+    }
+
+    if let Some(event_type) = TableEventType::from_raw(Event) {
+        (context.handler)(TableEvent {
+            event_type,
+            header: core::ptr::read_unaligned(Table as *const ACPI_TABLE_HEADER),
+        });
+    }
+
+    AE_OK
+}
@@ -0,0 +1,148 @@
+//! FPDT - Firmware Performance Data Table.
+//!
+//! The FPDT itself only holds pointers to the firmware basic boot performance table (FBPT)
+//! and the S3 performance table (S3PT), which live in firmware reserved memory and are
+//! reached through `AcpicaOsServices::map`.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+
+use super::{read, subtables, Table};
+use crate::{mapping::PhysicalMapping, AE_BAD_SIGNATURE, AE_INVALID_TABLE_LENGTH, AE_NOT_FOUND};
+
+const ACPI_FPDT_TYPE_BOOT: u16 = 0;
+const ACPI_FPDT_TYPE_S3PERF: u16 = 1;
+const ACPI_FPDT_BOOT_PERFORMANCE: u16 = 2;
+const ACPI_S3PT_TYPE_RESUME: u16 = 0;
+const ACPI_S3PT_TYPE_SUSPEND: u16 = 1;
+
+/// The pointer records found in the FPDT.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fpdt {
+    /// Physical address of the firmware basic boot performance table.
+    pub boot_table_address: Option<u64>,
+    /// Physical address of the S3 performance table.
+    pub s3_table_address: Option<u64>,
+}
+
+/// Firmware basic boot performance data record. All timestamps are in nanoseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BootPerformance {
+    pub reset_end: u64,
+    pub os_loader_load_image_start: u64,
+    pub os_loader_start_image_start: u64,
+    pub exit_boot_services_entry: u64,
+    pub exit_boot_services_exit: u64,
+}
+
+/// Records of the S3 performance table. All timestamps are in nanoseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct S3Performance {
+    pub resume_count: u32,
+    pub full_resume: u64,
+    pub average_resume: u64,
+    pub suspend_start: u64,
+    pub suspend_end: u64,
+}
+
+impl Fpdt {
+    /// Looks up and parses the FPDT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"FPDT", 1)?.bytes())
+    }
+
+    /// Parses an FPDT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let mut fpdt = Self::default();
+
+        for (record, data) in records(bytes, size_of::<ACPI_TABLE_FPDT>()) {
+            let Some(pointer) = read::<ACPI_FPDT_BOOT_POINTER>(data, 0) else {
+                continue;
+            };
+
+            match record.Type {
+                ACPI_FPDT_TYPE_BOOT => fpdt.boot_table_address = Some(pointer.Address),
+                ACPI_FPDT_TYPE_S3PERF => fpdt.s3_table_address = Some(pointer.Address),
+                _ => {}
+            }
+        }
+
+        Ok(fpdt)
+    }
+
+    /// Maps the firmware basic boot performance table and reads its boot record.
+    pub fn boot_performance(&self) -> Result<BootPerformance, ACPI_STATUS> {
+        let address = self.boot_table_address.ok_or(AE_NOT_FOUND)?;
+        let table = map_performance_table(address, b"FBPT")?;
+
+        records(table.as_slice(), size_of::<ACPI_TABLE_S3PT>())
+            .filter(|(record, _)| record.Type == ACPI_FPDT_BOOT_PERFORMANCE)
+            .find_map(|(_, data)| read::<ACPI_FPDT_BOOT>(data, 0))
+            .map(|boot| BootPerformance {
+                reset_end: boot.ResetEnd,
+                os_loader_load_image_start: boot.LoadStart,
+                os_loader_start_image_start: boot.StartupStart,
+                exit_boot_services_entry: boot.ExitServicesEntry,
+                exit_boot_services_exit: boot.ExitServicesExit,
+            })
+            .ok_or(AE_NOT_FOUND)
+    }
+
+    /// Maps the S3 performance table and reads its resume and suspend records.
+    pub fn s3_performance(&self) -> Result<S3Performance, ACPI_STATUS> {
+        let address = self.s3_table_address.ok_or(AE_NOT_FOUND)?;
+        let table = map_performance_table(address, b"S3PT")?;
+        let mut performance = S3Performance::default();
+
+        for (record, data) in records(table.as_slice(), size_of::<ACPI_TABLE_S3PT>()) {
+            match record.Type {
+                ACPI_S3PT_TYPE_RESUME => {
+                    if let Some(resume) = read::<ACPI_S3PT_RESUME>(data, 0) {
+                        performance.resume_count = resume.ResumeCount;
+                        performance.full_resume = resume.FullResume;
+                        performance.average_resume = resume.AverageResume;
+                    }
+                }
+                ACPI_S3PT_TYPE_SUSPEND => {
+                    if let Some(suspend) = read::<ACPI_S3PT_SUSPEND>(data, 0) {
+                        performance.suspend_start = suspend.SuspendStart;
+                        performance.suspend_end = suspend.SuspendEnd;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(performance)
+    }
+}
+
+/// Maps a performance table, first reading its header to find out its length.
+fn map_performance_table(
+    address: u64,
+    signature: &[u8; 4],
+) -> Result<PhysicalMapping, ACPI_STATUS> {
+    let header: ACPI_TABLE_S3PT = {
+        let mapping = PhysicalMapping::new(address, size_of::<ACPI_TABLE_S3PT>())?;
+        read(mapping.as_slice(), 0).ok_or(AE_INVALID_TABLE_LENGTH)?
+    };
+
+    if header.Signature != *signature {
+        return Err(AE_BAD_SIGNATURE);
+    }
+
+    let length = header.Length as usize;
+    if length < size_of::<ACPI_TABLE_S3PT>() {
+        return Err(AE_INVALID_TABLE_LENGTH);
+    }
+
+    PhysicalMapping::new(address, length)
+}
+
+/// Iterates over the performance records that follow a table header.
+fn records(bytes: &[u8], offset: usize) -> impl Iterator<Item = (ACPI_FPDT_HEADER, &[u8])> {
+    subtables(bytes, offset, |header: &ACPI_FPDT_HEADER| {
+        header.Length as usize
+    })
+}
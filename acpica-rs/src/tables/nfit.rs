@@ -0,0 +1,456 @@
+//! NFIT - NVDIMM Firmware Interface Table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use super::{read, read_fixed, read_prefix, subtables, Table};
+
+const ACPI_NFIT_TYPE_SYSTEM_ADDRESS: u16 = 0;
+const ACPI_NFIT_TYPE_MEMORY_MAP: u16 = 1;
+const ACPI_NFIT_TYPE_INTERLEAVE: u16 = 2;
+const ACPI_NFIT_TYPE_CONTROL_REGION: u16 = 4;
+const ACPI_NFIT_TYPE_DATA_REGION: u16 = 5;
+const ACPI_NFIT_TYPE_FLUSH_ADDRESS: u16 = 6;
+const ACPI_NFIT_TYPE_CAPABILITIES: u16 = 7;
+
+/// The fixed parts of `ACPI_NFIT_INTERLEAVE` and `ACPI_NFIT_FLUSH_ADDRESS`. bindgen doesn't
+/// derive `Copy` for the originals because they end in flexible arrays.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct InterleaveFixed {
+    header: ACPI_NFIT_HEADER,
+    interleave_index: u16,
+    reserved: u16,
+    line_count: u32,
+    line_size: u32,
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct FlushAddressFixed {
+    header: ACPI_NFIT_HEADER,
+    device_handle: u32,
+    hint_count: u16,
+    reserved: [u8; 6],
+}
+
+const _: () = assert!(size_of::<InterleaveFixed>() == size_of::<ACPI_NFIT_INTERLEAVE>());
+const _: () = assert!(size_of::<FlushAddressFixed>() == size_of::<ACPI_NFIT_FLUSH_ADDRESS>());
+
+// Address range type GUIDs, in the byte order they appear in the table.
+const VOLATILE_MEMORY: [u8; 16] = [
+    0x4f, 0x94, 0x05, 0x73, 0xda, 0xfd, 0xe3, 0x44, 0xb1, 0x6c, 0x3f, 0x22, 0xd2, 0x52, 0xe5, 0xd0,
+];
+const PERSISTENT_MEMORY: [u8; 16] = [
+    0x79, 0xd3, 0xf0, 0x66, 0xf3, 0xb4, 0x74, 0x40, 0xac, 0x43, 0x0d, 0x33, 0x18, 0xb7, 0x8c, 0xdb,
+];
+const CONTROL_REGION: [u8; 16] = [
+    0xf6, 0x01, 0xf7, 0x92, 0xb4, 0x13, 0x5d, 0x40, 0x91, 0x0b, 0x29, 0x93, 0x67, 0xe8, 0x23, 0x4c,
+];
+const DATA_REGION: [u8; 16] = [
+    0x30, 0x05, 0xaf, 0x91, 0x86, 0x5d, 0x0e, 0x47, 0xa6, 0xb0, 0x0a, 0x2d, 0xb9, 0x40, 0x82, 0x49,
+];
+const VOLATILE_VIRTUAL_DISK: [u8; 16] = [
+    0x5a, 0x53, 0xab, 0x77, 0xfc, 0x45, 0x4b, 0x62, 0x55, 0x60, 0xf7, 0xb2, 0x81, 0xd1, 0xf9, 0x6e,
+];
+const VOLATILE_VIRTUAL_CD: [u8; 16] = [
+    0x30, 0xbd, 0x5a, 0x3d, 0x75, 0x41, 0xce, 0x87, 0x6d, 0x64, 0xd2, 0xad, 0xe5, 0x23, 0xc4, 0xbb,
+];
+const PERSISTENT_VIRTUAL_DISK: [u8; 16] = [
+    0xc9, 0x02, 0xea, 0x5c, 0x07, 0x4d, 0xd3, 0x69, 0x26, 0x9f, 0x44, 0x96, 0xfb, 0xe0, 0x96, 0xf9,
+];
+const PERSISTENT_VIRTUAL_CD: [u8; 16] = [
+    0x88, 0x81, 0x01, 0x08, 0xcd, 0x42, 0x48, 0xbb, 0x10, 0x0f, 0x53, 0x87, 0xd5, 0x3d, 0xed, 0x3d,
+];
+
+/// What a system physical address range is used for, decoded from its range type GUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NfitRangeType {
+    VolatileMemory,
+    PersistentMemory,
+    ControlRegion,
+    BlockDataWindow,
+    VolatileVirtualDisk,
+    VolatileVirtualCd,
+    PersistentVirtualDisk,
+    PersistentVirtualCd,
+    Unknown,
+}
+
+impl NfitRangeType {
+    fn from_guid(guid: &[u8; 16]) -> Self {
+        match *guid {
+            VOLATILE_MEMORY => Self::VolatileMemory,
+            PERSISTENT_MEMORY => Self::PersistentMemory,
+            CONTROL_REGION => Self::ControlRegion,
+            DATA_REGION => Self::BlockDataWindow,
+            VOLATILE_VIRTUAL_DISK => Self::VolatileVirtualDisk,
+            VOLATILE_VIRTUAL_CD => Self::VolatileVirtualCd,
+            PERSISTENT_VIRTUAL_DISK => Self::PersistentVirtualDisk,
+            PERSISTENT_VIRTUAL_CD => Self::PersistentVirtualCd,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A system physical address (SPA) range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NfitSpaRange {
+    /// Index referenced by region mappings, never zero.
+    pub range_index: u16,
+    pub range_type: NfitRangeType,
+    pub range_guid: [u8; 16],
+    /// The range may only be added or onlined, not removed.
+    pub add_online_only: bool,
+    pub proximity_domain: Option<u32>,
+    pub address: u64,
+    pub length: u64,
+    /// EFI memory attributes (`EFI_MEMORY_*`) of the range.
+    pub memory_mapping_attributes: u64,
+    pub location_cookie: Option<u64>,
+}
+
+/// The `_ADR` of an NVDIMM, encoding its topological location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NfitDeviceHandle(pub u32);
+
+impl NfitDeviceHandle {
+    pub fn dimm(&self) -> u32 {
+        self.0 & ACPI_NFIT_DIMM_NUMBER_MASK
+    }
+
+    pub fn channel(&self) -> u32 {
+        (self.0 & ACPI_NFIT_CHANNEL_NUMBER_MASK) >> ACPI_NFIT_CHANNEL_NUMBER_OFFSET
+    }
+
+    pub fn memory_controller(&self) -> u32 {
+        (self.0 & ACPI_NFIT_MEMORY_ID_MASK) >> ACPI_NFIT_MEMORY_ID_OFFSET
+    }
+
+    pub fn socket(&self) -> u32 {
+        (self.0 & ACPI_NFIT_SOCKET_ID_MASK) >> ACPI_NFIT_SOCKET_ID_OFFSET
+    }
+
+    pub fn node(&self) -> u32 {
+        (self.0 & ACPI_NFIT_NODE_ID_MASK) >> ACPI_NFIT_NODE_ID_OFFSET
+    }
+}
+
+/// Health and state flags of an NVDIMM region mapping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NfitRegionFlags {
+    pub save_failed: bool,
+    pub restore_failed: bool,
+    pub flush_failed: bool,
+    pub not_armed: bool,
+    pub health_observed: bool,
+    pub health_enabled: bool,
+    pub map_failed: bool,
+}
+
+/// Maps a region of an NVDIMM into a system physical address range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NfitRegionMapping {
+    pub device_handle: NfitDeviceHandle,
+    pub physical_id: u16,
+    pub region_id: u16,
+    /// The SPA range this region is part of, or zero if it is not mapped.
+    pub range_index: u16,
+    /// The control region of the NVDIMM.
+    pub control_region_index: u16,
+    pub region_size: u64,
+    /// Offset of the region within the SPA range.
+    pub region_offset: u64,
+    /// Device physical address at which the region starts.
+    pub device_address: u64,
+    /// The interleave structure describing this region, or zero if it is not interleaved.
+    pub interleave_index: u16,
+    pub interleave_ways: u16,
+    pub flags: NfitRegionFlags,
+}
+
+/// Describes how lines of an interleave set are laid out on one NVDIMM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NfitInterleave {
+    pub interleave_index: u16,
+    pub line_size: u32,
+    /// Offset of each line, in multiples of `line_size`.
+    pub line_offsets: Vec<u32>,
+}
+
+/// Identification and register layout of an NVDIMM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NfitControlRegion {
+    pub region_index: u16,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub revision_id: u16,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_device_id: u16,
+    pub subsystem_revision_id: u16,
+    /// Manufacturing location and date, if the firmware reports them.
+    pub manufacturing: Option<(u8, u16)>,
+    pub serial_number: u32,
+    /// Register interface format code.
+    pub format_interface_code: u16,
+    /// Number of block data windows. Zero if the NVDIMM has none and the remaining
+    /// fields are not present.
+    pub windows: u16,
+    pub window_size: u64,
+    pub command_offset: u64,
+    pub command_size: u64,
+    pub status_offset: u64,
+    pub status_size: u64,
+    pub buffered: bool,
+}
+
+/// Block data windows of an NVDIMM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NfitDataRegion {
+    pub region_index: u16,
+    pub windows: u16,
+    pub offset: u64,
+    pub size: u64,
+    pub capacity: u64,
+    pub start_address: u64,
+}
+
+/// Addresses to write to in order to flush the write queues of an NVDIMM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NfitFlushHint {
+    pub device_handle: NfitDeviceHandle,
+    pub addresses: Vec<u64>,
+}
+
+/// Platform wide persistence capabilities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NfitCapabilities {
+    pub cache_flush: bool,
+    pub memory_flush: bool,
+    pub memory_mirroring: bool,
+}
+
+/// One NVDIMM region of a SPA range with the structures that describe it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedNvdimmRegion<'a> {
+    pub mapping: &'a NfitRegionMapping,
+    pub control_region: Option<&'a NfitControlRegion>,
+    pub interleave: Option<&'a NfitInterleave>,
+    pub flush_hint: Option<&'a NfitFlushHint>,
+}
+
+/// A SPA range together with the NVDIMM regions backing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSpaRange<'a> {
+    pub range: &'a NfitSpaRange,
+    pub regions: Vec<ResolvedNvdimmRegion<'a>>,
+}
+
+/// The NVDIMMs and address ranges described by the NFIT.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Nfit {
+    pub spa_ranges: Vec<NfitSpaRange>,
+    pub region_mappings: Vec<NfitRegionMapping>,
+    pub interleaves: Vec<NfitInterleave>,
+    pub control_regions: Vec<NfitControlRegion>,
+    pub data_regions: Vec<NfitDataRegion>,
+    pub flush_hints: Vec<NfitFlushHint>,
+    pub capabilities: Option<NfitCapabilities>,
+}
+
+impl Nfit {
+    /// Looks up and parses the NFIT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"NFIT", 1)?.bytes())
+    }
+
+    /// Parses an NFIT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let _: ACPI_TABLE_NFIT = read_fixed(bytes)?;
+        let mut nfit = Self::default();
+
+        let entries = subtables(
+            bytes,
+            size_of::<ACPI_TABLE_NFIT>(),
+            |header: &ACPI_NFIT_HEADER| header.Length as usize,
+        );
+
+        for (header, data) in entries {
+            match header.Type {
+                ACPI_NFIT_TYPE_SYSTEM_ADDRESS => {
+                    let spa: ACPI_NFIT_SYSTEM_ADDRESS = read_prefix(data);
+                    let flags = spa.Flags as u32;
+
+                    nfit.spa_ranges.push(NfitSpaRange {
+                        range_index: spa.RangeIndex,
+                        range_type: NfitRangeType::from_guid(&spa.RangeGuid),
+                        range_guid: spa.RangeGuid,
+                        add_online_only: flags & ACPI_NFIT_ADD_ONLINE_ONLY != 0,
+                        proximity_domain: (flags & ACPI_NFIT_PROXIMITY_VALID != 0)
+                            .then_some(spa.ProximityDomain),
+                        address: spa.Address,
+                        length: spa.Length,
+                        memory_mapping_attributes: spa.MemoryMapping,
+                        location_cookie: (flags & ACPI_NFIT_LOCATION_COOKIE_VALID != 0)
+                            .then_some(spa.LocationCookie),
+                    });
+                }
+                ACPI_NFIT_TYPE_MEMORY_MAP => {
+                    let Some(map) = read::<ACPI_NFIT_MEMORY_MAP>(data, 0) else {
+                        continue;
+                    };
+                    let flags = map.Flags as u32;
+
+                    nfit.region_mappings.push(NfitRegionMapping {
+                        device_handle: NfitDeviceHandle(map.DeviceHandle),
+                        physical_id: map.PhysicalId,
+                        region_id: map.RegionId,
+                        range_index: map.RangeIndex,
+                        control_region_index: map.RegionIndex,
+                        region_size: map.RegionSize,
+                        region_offset: map.RegionOffset,
+                        device_address: map.Address,
+                        interleave_index: map.InterleaveIndex,
+                        interleave_ways: map.InterleaveWays,
+                        flags: NfitRegionFlags {
+                            save_failed: flags & ACPI_NFIT_MEM_SAVE_FAILED != 0,
+                            restore_failed: flags & ACPI_NFIT_MEM_RESTORE_FAILED != 0,
+                            flush_failed: flags & ACPI_NFIT_MEM_FLUSH_FAILED != 0,
+                            not_armed: flags & ACPI_NFIT_MEM_NOT_ARMED != 0,
+                            health_observed: flags & ACPI_NFIT_MEM_HEALTH_OBSERVED != 0,
+                            health_enabled: flags & ACPI_NFIT_MEM_HEALTH_ENABLED != 0,
+                            map_failed: flags & ACPI_NFIT_MEM_MAP_FAILED != 0,
+                        },
+                    });
+                }
+                ACPI_NFIT_TYPE_INTERLEAVE => {
+                    let Some(interleave) = read::<InterleaveFixed>(data, 0) else {
+                        continue;
+                    };
+
+                    let line_offsets = (0..interleave.line_count as usize)
+                        .map_while(|i| read::<u32>(data, size_of::<ACPI_NFIT_INTERLEAVE>() + i * 4))
+                        .collect();
+
+                    nfit.interleaves.push(NfitInterleave {
+                        interleave_index: interleave.interleave_index,
+                        line_size: interleave.line_size,
+                        line_offsets,
+                    });
+                }
+                ACPI_NFIT_TYPE_CONTROL_REGION => {
+                    // The block data window fields are left out when there are no windows
+                    let control: ACPI_NFIT_CONTROL_REGION = read_prefix(data);
+
+                    let manufacturing =
+                        (control.ValidFields as u32 & ACPI_NFIT_CONTROL_MFG_INFO_VALID != 0)
+                            .then_some((control.ManufacturingLocation, control.ManufacturingDate));
+
+                    nfit.control_regions.push(NfitControlRegion {
+                        region_index: control.RegionIndex,
+                        vendor_id: control.VendorId,
+                        device_id: control.DeviceId,
+                        revision_id: control.RevisionId,
+                        subsystem_vendor_id: control.SubsystemVendorId,
+                        subsystem_device_id: control.SubsystemDeviceId,
+                        subsystem_revision_id: control.SubsystemRevisionId,
+                        manufacturing,
+                        serial_number: control.SerialNumber,
+                        format_interface_code: control.Code,
+                        windows: control.Windows,
+                        window_size: control.WindowSize,
+                        command_offset: control.CommandOffset,
+                        command_size: control.CommandSize,
+                        status_offset: control.StatusOffset,
+                        status_size: control.StatusSize,
+                        buffered: control.Flags as u32 & ACPI_NFIT_CONTROL_BUFFERED != 0,
+                    });
+                }
+                ACPI_NFIT_TYPE_DATA_REGION => {
+                    let Some(region) = read::<ACPI_NFIT_DATA_REGION>(data, 0) else {
+                        continue;
+                    };
+
+                    nfit.data_regions.push(NfitDataRegion {
+                        region_index: region.RegionIndex,
+                        windows: region.Windows,
+                        offset: region.Offset,
+                        size: region.Size,
+                        capacity: region.Capacity,
+                        start_address: region.StartAddress,
+                    });
+                }
+                ACPI_NFIT_TYPE_FLUSH_ADDRESS => {
+                    let Some(flush) = read::<FlushAddressFixed>(data, 0) else {
+                        continue;
+                    };
+
+                    let addresses = (0..flush.hint_count as usize)
+                        .map_while(|i| {
+                            read::<u64>(data, size_of::<ACPI_NFIT_FLUSH_ADDRESS>() + i * 8)
+                        })
+                        .collect();
+
+                    nfit.flush_hints.push(NfitFlushHint {
+                        device_handle: NfitDeviceHandle(flush.device_handle),
+                        addresses,
+                    });
+                }
+                ACPI_NFIT_TYPE_CAPABILITIES => {
+                    let Some(capabilities) = read::<ACPI_NFIT_CAPABILITIES>(data, 0) else {
+                        continue;
+                    };
+                    let flags = capabilities.Capabilities;
+
+                    nfit.capabilities = Some(NfitCapabilities {
+                        cache_flush: flags & ACPI_NFIT_CAPABILITY_CACHE_FLUSH != 0,
+                        memory_flush: flags & ACPI_NFIT_CAPABILITY_MEM_FLUSH != 0,
+                        memory_mirroring: flags & ACPI_NFIT_CAPABILITY_MEM_MIRRORING != 0,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(nfit)
+    }
+
+    /// Maps each SPA range to the NVDIMM regions backing it.
+    pub fn resolve(&self) -> Vec<ResolvedSpaRange<'_>> {
+        self.spa_ranges
+            .iter()
+            .map(|range| ResolvedSpaRange {
+                range,
+                regions: self
+                    .region_mappings
+                    .iter()
+                    .filter(|mapping| mapping.range_index == range.range_index)
+                    .map(|mapping| self.resolve_region(mapping))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn resolve_region<'a>(&'a self, mapping: &'a NfitRegionMapping) -> ResolvedNvdimmRegion<'a> {
+        ResolvedNvdimmRegion {
+            mapping,
+            control_region: self
+                .control_regions
+                .iter()
+                .find(|control| control.region_index == mapping.control_region_index),
+            interleave: (mapping.interleave_index != 0)
+                .then(|| {
+                    self.interleaves
+                        .iter()
+                        .find(|interleave| interleave.interleave_index == mapping.interleave_index)
+                })
+                .flatten(),
+            flush_hint: self
+                .flush_hints
+                .iter()
+                .find(|hint| hint.device_handle == mapping.device_handle),
+        }
+    }
+}
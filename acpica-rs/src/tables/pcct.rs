@@ -0,0 +1,284 @@
+//! PCCT - Platform Communications Channel Table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use super::{read, read_fixed, subtables, GenericAddress, Table};
+
+const ACPI_PCCT_TYPE_GENERIC_SUBSPACE: u8 = 0;
+const ACPI_PCCT_TYPE_HW_REDUCED_SUBSPACE: u8 = 1;
+const ACPI_PCCT_TYPE_HW_REDUCED_SUBSPACE_TYPE2: u8 = 2;
+const ACPI_PCCT_TYPE_EXT_PCC_MASTER_SUBSPACE: u8 = 3;
+const ACPI_PCCT_TYPE_EXT_PCC_SLAVE_SUBSPACE: u8 = 4;
+const ACPI_PCCT_TYPE_HW_REG_COMM_SUBSPACE: u8 = 5;
+
+/// The kind of a PCC subspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PccSubspaceType {
+    Generic,
+    HwReduced,
+    HwReducedType2,
+    /// Extended subspace used by the OS to send commands to the platform.
+    ExtendedInitiator,
+    /// Extended subspace used by the platform to send notifications to the OS.
+    ExtendedResponder,
+    HwRegisters,
+}
+
+/// A register that is updated by a read-modify-write: bits outside `preserve_mask` are
+/// cleared before `set_mask` is ORed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PccWriteRegister {
+    pub register: GenericAddress,
+    pub preserve_mask: u64,
+    pub set_mask: u64,
+}
+
+/// A register whose state is checked through the bits in `mask`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PccStatusRegister {
+    pub register: GenericAddress,
+    pub mask: u64,
+}
+
+/// The interrupt the platform raises to signal command completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PccInterrupt {
+    pub gsi: u32,
+    pub active_low: bool,
+    pub edge_triggered: bool,
+}
+
+/// A PCC subspace: a shared memory region plus the registers used to signal through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PccSubspace {
+    /// Index of the subspace within the PCCT, used by `_CPC`, PCC operation regions etc.
+    pub id: u8,
+    pub subspace_type: PccSubspaceType,
+    pub base_address: u64,
+    pub length: u64,
+    pub doorbell: PccWriteRegister,
+    pub platform_interrupt: Option<PccInterrupt>,
+    /// Register used to acknowledge the platform interrupt.
+    pub platform_ack: Option<PccWriteRegister>,
+    /// Register signalling command completion. Subspaces without one report completion
+    /// through the status field of their shared memory.
+    pub command_complete: Option<PccStatusRegister>,
+    /// Register used to tell the platform the command field was updated.
+    pub command_update: Option<PccWriteRegister>,
+    pub error_status: Option<PccStatusRegister>,
+    /// Expected latency of a command, in microseconds.
+    pub latency: u32,
+    /// Maximum number of commands the platform accepts per minute, zero if unlimited.
+    pub max_access_rate: u32,
+    /// Minimum time between the end of one command and the start of the next, in microseconds.
+    pub min_turnaround_time: u32,
+}
+
+/// The communication channels described by the PCCT.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pcct {
+    /// The platform signals command completion through an SCI.
+    pub sci_doorbell: bool,
+    pub subspaces: Vec<PccSubspace>,
+}
+
+impl Pcct {
+    /// Looks up and parses the PCCT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"PCCT", 1)?.bytes())
+    }
+
+    /// Parses a PCCT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: ACPI_TABLE_PCCT = read_fixed(bytes)?;
+        let mut subspaces = Vec::new();
+
+        let entries = subtables(
+            bytes,
+            size_of::<ACPI_TABLE_PCCT>(),
+            |header: &ACPI_SUBTABLE_HEADER| header.Length as usize,
+        );
+
+        // Subspace IDs are positional, so unparsable subspaces still take up an ID
+        for (id, (header, data)) in entries.enumerate() {
+            if let Some(subspace) = parse_subspace(id as u8, header.Type, data) {
+                subspaces.push(subspace);
+            }
+        }
+
+        Ok(Self {
+            sci_doorbell: table.Flags & ACPI_PCCT_DOORBELL != 0,
+            subspaces,
+        })
+    }
+
+    /// Looks up a subspace by its ID.
+    pub fn subspace(&self, id: u8) -> Option<&PccSubspace> {
+        self.subspaces.iter().find(|subspace| subspace.id == id)
+    }
+}
+
+fn interrupt(gsi: u32, flags: u8) -> PccInterrupt {
+    let flags = flags as u32;
+
+    PccInterrupt {
+        gsi,
+        active_low: flags & ACPI_PCCT_INTERRUPT_POLARITY != 0,
+        edge_triggered: flags & ACPI_PCCT_INTERRUPT_MODE != 0,
+    }
+}
+
+fn parse_subspace(id: u8, subspace_type: u8, data: &[u8]) -> Option<PccSubspace> {
+    match subspace_type {
+        ACPI_PCCT_TYPE_GENERIC_SUBSPACE => {
+            let subspace: ACPI_PCCT_SUBSPACE = read(data, 0)?;
+
+            Some(PccSubspace {
+                id,
+                subspace_type: PccSubspaceType::Generic,
+                base_address: subspace.BaseAddress,
+                length: subspace.Length,
+                doorbell: PccWriteRegister {
+                    register: subspace.DoorbellRegister.into(),
+                    preserve_mask: subspace.PreserveMask,
+                    set_mask: subspace.WriteMask,
+                },
+                platform_interrupt: None,
+                platform_ack: None,
+                command_complete: None,
+                command_update: None,
+                error_status: None,
+                latency: subspace.Latency,
+                max_access_rate: subspace.MaxAccessRate,
+                min_turnaround_time: subspace.MinTurnaroundTime as u32,
+            })
+        }
+        ACPI_PCCT_TYPE_HW_REDUCED_SUBSPACE => {
+            let subspace: ACPI_PCCT_HW_REDUCED = read(data, 0)?;
+
+            Some(PccSubspace {
+                id,
+                subspace_type: PccSubspaceType::HwReduced,
+                base_address: subspace.BaseAddress,
+                length: subspace.Length,
+                doorbell: PccWriteRegister {
+                    register: subspace.DoorbellRegister.into(),
+                    preserve_mask: subspace.PreserveMask,
+                    set_mask: subspace.WriteMask,
+                },
+                platform_interrupt: Some(interrupt(subspace.PlatformInterrupt, subspace.Flags)),
+                platform_ack: None,
+                command_complete: None,
+                command_update: None,
+                error_status: None,
+                latency: subspace.Latency,
+                max_access_rate: subspace.MaxAccessRate,
+                min_turnaround_time: subspace.MinTurnaroundTime as u32,
+            })
+        }
+        ACPI_PCCT_TYPE_HW_REDUCED_SUBSPACE_TYPE2 => {
+            let subspace: ACPI_PCCT_HW_REDUCED_TYPE2 = read(data, 0)?;
+
+            Some(PccSubspace {
+                id,
+                subspace_type: PccSubspaceType::HwReducedType2,
+                base_address: subspace.BaseAddress,
+                length: subspace.Length,
+                doorbell: PccWriteRegister {
+                    register: subspace.DoorbellRegister.into(),
+                    preserve_mask: subspace.PreserveMask,
+                    set_mask: subspace.WriteMask,
+                },
+                platform_interrupt: Some(interrupt(subspace.PlatformInterrupt, subspace.Flags)),
+                platform_ack: Some(PccWriteRegister {
+                    register: subspace.PlatformAckRegister.into(),
+                    preserve_mask: subspace.AckPreserveMask,
+                    set_mask: subspace.AckWriteMask,
+                }),
+                command_complete: None,
+                command_update: None,
+                error_status: None,
+                latency: subspace.Latency,
+                max_access_rate: subspace.MaxAccessRate,
+                min_turnaround_time: subspace.MinTurnaroundTime as u32,
+            })
+        }
+        ACPI_PCCT_TYPE_EXT_PCC_MASTER_SUBSPACE | ACPI_PCCT_TYPE_EXT_PCC_SLAVE_SUBSPACE => {
+            // Both extended subspace types share the same layout
+            let subspace: ACPI_PCCT_EXT_PCC_MASTER = read(data, 0)?;
+
+            let subspace_type = if subspace_type == ACPI_PCCT_TYPE_EXT_PCC_MASTER_SUBSPACE {
+                PccSubspaceType::ExtendedInitiator
+            } else {
+                PccSubspaceType::ExtendedResponder
+            };
+
+            Some(PccSubspace {
+                id,
+                subspace_type,
+                base_address: subspace.BaseAddress,
+                length: subspace.Length as u64,
+                doorbell: PccWriteRegister {
+                    register: subspace.DoorbellRegister.into(),
+                    preserve_mask: subspace.PreserveMask,
+                    set_mask: subspace.WriteMask,
+                },
+                platform_interrupt: Some(interrupt(subspace.PlatformInterrupt, subspace.Flags)),
+                platform_ack: Some(PccWriteRegister {
+                    register: subspace.PlatformAckRegister.into(),
+                    preserve_mask: subspace.AckPreserveMask,
+                    set_mask: subspace.AckSetMask,
+                }),
+                command_complete: Some(PccStatusRegister {
+                    register: subspace.CmdCompleteRegister.into(),
+                    mask: subspace.CmdCompleteMask,
+                }),
+                command_update: Some(PccWriteRegister {
+                    register: subspace.CmdUpdateRegister.into(),
+                    preserve_mask: subspace.CmdUpdatePreserveMask,
+                    set_mask: subspace.CmdUpdateSetMask,
+                }),
+                error_status: Some(PccStatusRegister {
+                    register: subspace.ErrorStatusRegister.into(),
+                    mask: subspace.ErrorStatusMask,
+                }),
+                latency: subspace.Latency,
+                max_access_rate: subspace.MaxAccessRate,
+                min_turnaround_time: subspace.MinTurnaroundTime,
+            })
+        }
+        ACPI_PCCT_TYPE_HW_REG_COMM_SUBSPACE => {
+            let subspace: ACPI_PCCT_HW_REG = read(data, 0)?;
+
+            Some(PccSubspace {
+                id,
+                subspace_type: PccSubspaceType::HwRegisters,
+                base_address: subspace.BaseAddress,
+                length: subspace.Length,
+                doorbell: PccWriteRegister {
+                    register: subspace.DoorbellRegister.into(),
+                    preserve_mask: subspace.DoorbellPreserve,
+                    set_mask: subspace.DoorbellWrite,
+                },
+                platform_interrupt: None,
+                platform_ack: None,
+                command_complete: Some(PccStatusRegister {
+                    register: subspace.CmdCompleteRegister.into(),
+                    mask: subspace.CmdCompleteMask,
+                }),
+                command_update: None,
+                error_status: Some(PccStatusRegister {
+                    register: subspace.ErrorStatusRegister.into(),
+                    mask: subspace.ErrorStatusMask,
+                }),
+                latency: subspace.NominalLatency,
+                max_access_rate: 0,
+                min_turnaround_time: subspace.MinTurnaroundTime,
+            })
+        }
+        _ => None,
+    }
+}
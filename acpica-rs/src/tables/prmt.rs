@@ -0,0 +1,106 @@
+//! PRMT - Platform Runtime Mechanism Table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use super::{read, read_fixed, read_prefix, subtables, Table};
+use crate::AE_INVALID_TABLE_LENGTH;
+
+/// A PRM handler: firmware code the OS runs on behalf of AML or drivers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrmHandler {
+    pub guid: [u8; 16],
+    /// Physical address of the handler entry point.
+    pub address: u64,
+    /// Physical address of the static data buffer, or zero.
+    pub static_data_buffer_address: u64,
+    /// Physical address of the ACPI parameter buffer, or zero.
+    pub acpi_param_buffer_address: u64,
+}
+
+/// A PRM module: a firmware update unit containing a set of handlers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrmModule {
+    pub guid: [u8; 16],
+    pub major_revision: u16,
+    pub minor_revision: u16,
+    /// Physical address of the runtime MMIO range list, or zero.
+    pub mmio_list_address: u64,
+    pub handlers: Vec<PrmHandler>,
+}
+
+/// The PRM modules and handlers the firmware provides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prmt {
+    pub platform_guid: [u8; 16],
+    pub modules: Vec<PrmModule>,
+}
+
+impl Prmt {
+    /// Looks up and parses the PRMT.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"PRMT", 1)?.bytes())
+    }
+
+    /// Parses a PRMT from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let _: ACPI_TABLE_PRMT = read_fixed(bytes)?;
+        let header: ACPI_TABLE_PRMT_HEADER =
+            read(bytes, size_of::<ACPI_TABLE_PRMT>()).ok_or(AE_INVALID_TABLE_LENGTH)?;
+
+        let modules = subtables(
+            bytes,
+            header.ModuleInfoOffset as usize,
+            |module: &ACPI_PRMT_MODULE_HEADER| module.Length as usize,
+        )
+        .take(header.ModuleInfoCount as usize)
+        .map(|(_, data)| {
+            let module: ACPI_PRMT_MODULE_INFO = read_prefix(data);
+
+            let handlers = subtables(
+                data,
+                module.HandlerInfoOffset as usize,
+                |handler: &ACPI_PRMT_MODULE_HEADER| handler.Length as usize,
+            )
+            .take(module.HandlerInfoCount as usize)
+            .map(|(_, data)| {
+                let handler: ACPI_PRMT_HANDLER_INFO = read_prefix(data);
+
+                PrmHandler {
+                    guid: handler.HandlerGuid,
+                    address: handler.HandlerAddress,
+                    static_data_buffer_address: handler.StaticDataBufferAddress,
+                    acpi_param_buffer_address: handler.AcpiParamBufferAddress,
+                }
+            })
+            .collect();
+
+            PrmModule {
+                guid: module.ModuleGuid,
+                major_revision: module.MajorRev,
+                minor_revision: module.MinorRev,
+                mmio_list_address: module.MmioListPointer,
+                handlers,
+            }
+        })
+        .collect();
+
+        Ok(Self {
+            platform_guid: header.PlatformGuid,
+            modules,
+        })
+    }
+
+    /// Looks up a handler by its GUID, together with the module that contains it.
+    pub fn handler(&self, guid: &[u8; 16]) -> Option<(&PrmModule, &PrmHandler)> {
+        self.modules.iter().find_map(|module| {
+            module
+                .handlers
+                .iter()
+                .find(|handler| handler.guid == *guid)
+                .map(|handler| (module, handler))
+        })
+    }
+}
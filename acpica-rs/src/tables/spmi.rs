@@ -0,0 +1,90 @@
+//! SPMI - Server Platform Management Interface table.
+
+use acpica_sys::*;
+
+use super::{read_fixed, GenericAddress, Table};
+
+/// Values of `InterfaceType`.
+const ACPI_SPMI_KEYBOARD: u8 = 1;
+const ACPI_SPMI_SMI: u8 = 2;
+const ACPI_SPMI_BLOCK_TRANSFER: u8 = 3;
+const ACPI_SPMI_SMBUS: u8 = 4;
+
+/// Bits of `InterruptType` telling how the interface signals.
+const SPMI_INTERRUPT_SCI: u8 = 1 << 0;
+const SPMI_INTERRUPT_IO_APIC: u8 = 1 << 1;
+
+/// `PciDeviceFlag` bit telling that the PCI location fields are valid.
+const SPMI_PCI_DEVICE: u8 = 1 << 0;
+
+/// The IPMI system interface described by the SPMI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpmiInterfaceType {
+    Keyboard,
+    ServerManagementInterfaceChip,
+    BlockTransfer,
+    SmBus,
+    Reserved(u8),
+}
+
+/// PCI location of the IPMI interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpmiPciDevice {
+    pub segment: u8,
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+}
+
+/// Describes the IPMI baseboard management controller interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spmi {
+    pub interface_type: SpmiInterfaceType,
+    /// IPMI specification revision, in BCD (e.g. `0x0200` for IPMI 2.0).
+    pub spec_revision: u16,
+    /// The GPE used by the interface, if it signals through an SCI.
+    pub gpe: Option<u8>,
+    /// The global system interrupt used by the interface, if it signals through an I/O APIC.
+    pub interrupt: Option<u32>,
+    /// Base address of the interface registers.
+    pub register: GenericAddress,
+    /// PCI location of the interface, if it is a PCI device.
+    pub pci_device: Option<SpmiPciDevice>,
+}
+
+impl Spmi {
+    /// Looks up and parses the SPMI.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"SPMI", 1)?.bytes())
+    }
+
+    /// Parses an SPMI from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: ACPI_TABLE_SPMI = read_fixed(bytes)?;
+
+        let interface_type = match table.InterfaceType {
+            ACPI_SPMI_KEYBOARD => SpmiInterfaceType::Keyboard,
+            ACPI_SPMI_SMI => SpmiInterfaceType::ServerManagementInterfaceChip,
+            ACPI_SPMI_BLOCK_TRANSFER => SpmiInterfaceType::BlockTransfer,
+            ACPI_SPMI_SMBUS => SpmiInterfaceType::SmBus,
+            other => SpmiInterfaceType::Reserved(other),
+        };
+
+        let pci_device = (table.PciDeviceFlag & SPMI_PCI_DEVICE != 0).then_some(SpmiPciDevice {
+            segment: table.PciSegment,
+            bus: table.PciBus,
+            device: table.PciDevice,
+            function: table.PciFunction,
+        });
+
+        Ok(Self {
+            interface_type,
+            spec_revision: table.SpecRevision,
+            gpe: (table.InterruptType & SPMI_INTERRUPT_SCI != 0).then_some(table.GpeNumber),
+            interrupt: (table.InterruptType & SPMI_INTERRUPT_IO_APIC != 0)
+                .then_some(table.Interrupt),
+            register: table.IpmiRegister.into(),
+            pci_device,
+        })
+    }
+}
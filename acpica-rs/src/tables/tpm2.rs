@@ -0,0 +1,149 @@
+//! TPM2 - Trusted Platform Module 2.0 hardware interface table.
+
+use core::mem::size_of;
+
+use acpica_sys::*;
+
+use super::{read, read_fixed, Table};
+use crate::{mapping::PhysicalMapping, AE_INVALID_TABLE_LENGTH, AE_SUPPORT};
+
+/// Size of the start method specific parameters that precede the optional log area fields.
+const START_METHOD_PARAMETERS_SIZE: usize = 12;
+
+/// Size of the CRB control area, up to and including the response buffer address.
+const CONTROL_AREA_SIZE: usize = 48;
+
+/// How the OS tells the TPM to start processing a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tpm2StartMethod {
+    /// Start through the `_DSM` method of the TPM device.
+    Acpi,
+    /// FIFO (TIS) interface, no start method needed.
+    MemoryMapped,
+    /// Command response buffer interface.
+    CommandBuffer,
+    /// Command response buffer interface, started through `_DSM`.
+    CommandBufferWithAcpi,
+    /// Command response buffer interface, started through an Arm SMC call.
+    CommandBufferWithArmSmc(Tpm2ArmSmc),
+    Reserved(u32),
+}
+
+/// Parameters for the Arm SMC start method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tpm2ArmSmc {
+    /// The interrupt used to signal command completion, if interrupts are supported.
+    pub interrupt: Option<u32>,
+    pub idle_support: bool,
+    pub function_id: u32,
+}
+
+/// Location of the TCG event log area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tpm2Log {
+    pub minimum_length: u32,
+    pub address: u64,
+}
+
+/// Describes how to reach the platform's TPM 2.0 device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tpm2 {
+    pub revision: u8,
+    /// Zero for client platforms, one for servers. Only meaningful from revision 4 on.
+    pub platform_class: u16,
+    /// Physical address of the CRB control area, or of the FIFO registers.
+    pub control_address: u64,
+    pub start_method: Tpm2StartMethod,
+    /// Raw start method specific parameters.
+    pub start_method_parameters: [u8; START_METHOD_PARAMETERS_SIZE],
+    pub log: Option<Tpm2Log>,
+}
+
+/// The buffer locations published by the TPM in its CRB control area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tpm2ControlArea {
+    pub command_size: u32,
+    pub command_address: u64,
+    pub response_size: u32,
+    pub response_address: u64,
+}
+
+impl Tpm2 {
+    /// Looks up and parses the TPM2 table.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"TPM2", 1)?.bytes())
+    }
+
+    /// Parses a TPM2 table from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: ACPI_TABLE_TPM2 = read_fixed(bytes)?;
+        let header = table.Header;
+
+        let mut start_method_parameters = [0; START_METHOD_PARAMETERS_SIZE];
+        let parameters = bytes
+            .get(size_of::<ACPI_TABLE_TPM2>()..)
+            .unwrap_or_default();
+        let count = parameters.len().min(START_METHOD_PARAMETERS_SIZE);
+        start_method_parameters[..count].copy_from_slice(&parameters[..count]);
+
+        let start_method = match table.StartMethod {
+            ACPI_TPM2_START_METHOD => Tpm2StartMethod::Acpi,
+            ACPI_TPM2_MEMORY_MAPPED => Tpm2StartMethod::MemoryMapped,
+            ACPI_TPM2_COMMAND_BUFFER => Tpm2StartMethod::CommandBuffer,
+            ACPI_TPM2_COMMAND_BUFFER_WITH_START_METHOD => Tpm2StartMethod::CommandBufferWithAcpi,
+            ACPI_TPM2_COMMAND_BUFFER_WITH_ARM_SMC => {
+                let smc: ACPI_TPM2_ARM_SMC =
+                    read(bytes, size_of::<ACPI_TABLE_TPM2>()).ok_or(AE_INVALID_TABLE_LENGTH)?;
+
+                Tpm2StartMethod::CommandBufferWithArmSmc(Tpm2ArmSmc {
+                    interrupt: (smc.InterruptFlags as u32 & ACPI_TPM2_INTERRUPT_SUPPORT != 0)
+                        .then_some(smc.GlobalInterrupt),
+                    idle_support: smc.OperationFlags as u32 & ACPI_TPM2_IDLE_SUPPORT != 0,
+                    function_id: smc.FunctionId,
+                })
+            }
+            other => Tpm2StartMethod::Reserved(other),
+        };
+
+        let log =
+            read::<ACPI_TPM2_TRAILER>(bytes, size_of::<ACPI_TABLE_TPM2>()).map(|trailer| Tpm2Log {
+                minimum_length: trailer.MinimumLogLength,
+                address: trailer.LogAddress,
+            });
+
+        Ok(Self {
+            revision: header.Revision,
+            platform_class: table.PlatformClass,
+            control_address: table.ControlAddress,
+            start_method,
+            start_method_parameters,
+            log,
+        })
+    }
+
+    /// Maps the CRB control area and reads the command and response buffer locations.
+    ///
+    /// Fails with `AE_SUPPORT` if the TPM does not use the command response buffer interface.
+    pub fn control_area(&self) -> Result<Tpm2ControlArea, ACPI_STATUS> {
+        match self.start_method {
+            Tpm2StartMethod::CommandBuffer
+            | Tpm2StartMethod::CommandBufferWithAcpi
+            | Tpm2StartMethod::CommandBufferWithArmSmc(_) => {}
+            _ => return Err(AE_SUPPORT),
+        }
+
+        let mapping = PhysicalMapping::new(self.control_address, CONTROL_AREA_SIZE)?;
+        let registers = mapping.as_ptr();
+
+        let read_u32 = |offset: usize| unsafe {
+            core::ptr::read_volatile(registers.add(offset) as *const u32)
+        };
+
+        Ok(Tpm2ControlArea {
+            command_size: read_u32(24),
+            command_address: read_u32(28) as u64 | (read_u32(32) as u64) << 32,
+            response_size: read_u32(36),
+            response_address: read_u32(40) as u64 | (read_u32(44) as u64) << 32,
+        })
+    }
+}
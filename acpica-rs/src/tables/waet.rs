@@ -0,0 +1,31 @@
+//! WAET - Windows ACPI Emulated devices Table.
+
+use acpica_sys::*;
+
+use super::{read_fixed, Table};
+
+/// Hints that allow a guest to skip work for emulated devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Waet {
+    /// The RTC does not need its interrupt acknowledged by reading register C.
+    pub rtc_no_ack: bool,
+    /// The PM timer is reliable and only needs to be read once.
+    pub pm_timer_one_read: bool,
+}
+
+impl Waet {
+    /// Looks up and parses the WAET.
+    pub fn get() -> Result<Self, ACPI_STATUS> {
+        Self::parse(Table::get(b"WAET", 1)?.bytes())
+    }
+
+    /// Parses a WAET from its raw bytes, including the table header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ACPI_STATUS> {
+        let table: ACPI_TABLE_WAET = read_fixed(bytes)?;
+
+        Ok(Self {
+            rtc_no_ack: table.Flags & ACPI_WAET_RTC_NO_ACK != 0,
+            pm_timer_one_read: table.Flags & ACPI_WAET_TIMER_ONE_READ != 0,
+        })
+    }
+}
@@ -0,0 +1,408 @@
+//! Thermal zones.
+//!
+//! A `ThermalZone` object reports its temperature through `_TMP` and the temperatures at
+//! which the OS has to act through its trip points: `_CRT` to shut down, `_HOT` to enter
+//! S4, `_PSV` to throttle the processors in `_PSL` and `_AC0` to `_AC9` to turn on the
+//! fans in the matching `_AL0` to `_AL9`. Temperatures are in tenths of a Kelvin.
+
+use acpica_sys::*;
+use alloc::{format, vec, vec::Vec};
+use spin::Mutex;
+
+use crate::{
+    current_os_services_implementation,
+    device_power::DevicePower,
+    namespace::{self, Handle, Object},
+    notify::{Notify, NotifySubscription, NotifyType},
+    power::DeviceState,
+    AE_NOT_FOUND,
+};
+
+/// The `_HID` of fan devices.
+pub const FAN_HID: &str = "PNP0C0B";
+
+/// Thermal zone notification values.
+const THERMAL_NOTIFY_TEMPERATURE: u8 = 0x80;
+const THERMAL_NOTIFY_TRIP_POINTS: u8 = 0x81;
+const THERMAL_NOTIFY_DEVICES: u8 = 0x82;
+const THERMAL_NOTIFY_RELATIONSHIP_TABLE: u8 = 0x83;
+
+/// Number of active trip points a thermal zone can have.
+const MAX_ACTIVE_TRIP_POINTS: usize = 10;
+
+/// How long to wait before checking again whether another update of the fans finished, in
+/// milliseconds.
+const FAN_UPDATE_WAIT: u64 = 1;
+
+/// 0 °C in tenths of a Kelvin.
+const ZERO_CELSIUS: i32 = 2732;
+
+/// A temperature, stored in tenths of a Kelvin like in AML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Temperature(u32);
+
+impl Temperature {
+    pub const fn from_deci_kelvin(deci_kelvin: u32) -> Self {
+        Self(deci_kelvin)
+    }
+
+    pub const fn deci_kelvin(self) -> u32 {
+        self.0
+    }
+
+    /// Converts to tenths of a degree Celsius, using the 273.2 K offset AML uses.
+    pub const fn deci_celsius(self) -> i32 {
+        self.0 as i32 - ZERO_CELSIUS
+    }
+
+    pub const fn from_deci_celsius(deci_celsius: i32) -> Self {
+        Self((deci_celsius + ZERO_CELSIUS) as u32)
+    }
+
+    pub const fn millicelsius(self) -> i32 {
+        self.deci_celsius() * 100
+    }
+}
+
+/// The meaning of a notification sent to a thermal zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThermalEvent {
+    /// The temperature crossed a trip point, read `_TMP` again.
+    TemperatureChanged,
+    /// The trip points changed, read them again.
+    TripPointsChanged,
+    /// The `_PSL`, `_ALx` or `_TZD` device lists changed.
+    DevicesChanged,
+    /// The thermal relationship table `_TRT` changed.
+    RelationshipTableChanged,
+    /// Any other notification.
+    Other(Notify),
+}
+
+impl ThermalEvent {
+    pub const fn from_notify(notify: Notify) -> Self {
+        match notify {
+            Notify::DeviceSpecific(THERMAL_NOTIFY_TEMPERATURE) => Self::TemperatureChanged,
+            Notify::DeviceSpecific(THERMAL_NOTIFY_TRIP_POINTS) => Self::TripPointsChanged,
+            Notify::DeviceSpecific(THERMAL_NOTIFY_DEVICES) => Self::DevicesChanged,
+            Notify::DeviceSpecific(THERMAL_NOTIFY_RELATIONSHIP_TABLE) => {
+                Self::RelationshipTableChanged
+            }
+            notify => Self::Other(notify),
+        }
+    }
+}
+
+/// An active trip point and the fans to turn on above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveTripPoint {
+    pub temperature: Temperature,
+    pub devices: Vec<Handle>,
+}
+
+/// The trip points of a thermal zone. Trip points the zone does not have are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripPoints {
+    /// The OS shuts down above this temperature.
+    pub critical: Option<Temperature>,
+    /// The OS enters S4 above this temperature.
+    pub hot: Option<Temperature>,
+    /// The OS throttles the processors of `_PSL` above this temperature.
+    pub passive: Option<Temperature>,
+    /// From `_AC0`, the highest, downwards.
+    pub active: Vec<ActiveTripPoint>,
+}
+
+/// The constants of the passive cooling formula.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PassiveCooling {
+    /// `_TC1`.
+    pub tc1: u32,
+    /// `_TC2`.
+    pub tc2: u32,
+    /// `_TSP`, the period in tenths of a second at which to apply the formula.
+    pub sampling_period: u32,
+}
+
+impl PassiveCooling {
+    /// Returns by how many percent to reduce the performance of the processors, given the
+    /// temperature now and one sampling period ago. Negative values allow more performance.
+    pub fn performance_reduction(
+        &self,
+        current: Temperature,
+        last: Temperature,
+        passive: Temperature,
+    ) -> i64 {
+        self.tc1 as i64 * (current.0 as i64 - last.0 as i64)
+            + self.tc2 as i64 * (current.0 as i64 - passive.0 as i64)
+    }
+}
+
+/// A thermal zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThermalZone {
+    handle: Handle,
+}
+
+impl ThermalZone {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Finds all thermal zones in the namespace.
+    pub fn all() -> Result<Vec<Self>, ACPI_STATUS> {
+        Ok(namespace::find_objects(ACPI_TYPE_THERMAL)?
+            .into_iter()
+            .map(Self::new)
+            .collect())
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Evaluates `_TMP`, the current temperature.
+    pub fn temperature(&self) -> Result<Temperature, ACPI_STATUS> {
+        self.temperature_object("_TMP")?.ok_or(AE_NOT_FOUND)
+    }
+
+    /// Evaluates `_CRT`, `_HOT`, `_PSV`, `_ACx` and `_ALx`.
+    pub fn trip_points(&self) -> Result<TripPoints, ACPI_STATUS> {
+        let mut active = Vec::new();
+
+        // The active trip points have to be contiguous from _AC0
+        for i in 0..MAX_ACTIVE_TRIP_POINTS {
+            let Some(temperature) = self.temperature_object(&format!("_AC{}", i))? else {
+                break;
+            };
+
+            active.push(ActiveTripPoint {
+                temperature,
+                devices: self.device_list(&format!("_AL{}", i))?,
+            });
+        }
+
+        Ok(TripPoints {
+            critical: self.temperature_object("_CRT")?,
+            hot: self.temperature_object("_HOT")?,
+            passive: self.temperature_object("_PSV")?,
+            active,
+        })
+    }
+
+    /// Evaluates `_PSL`, the processors to throttle for passive cooling.
+    pub fn passive_devices(&self) -> Result<Vec<Handle>, ACPI_STATUS> {
+        self.device_list("_PSL")
+    }
+
+    /// Evaluates `_TC1`, `_TC2` and `_TSP`. Fails with `AE_NOT_FOUND` if the zone doesn't
+    /// do passive cooling.
+    pub fn passive_cooling(&self) -> Result<PassiveCooling, ACPI_STATUS> {
+        Ok(PassiveCooling {
+            tc1: self.handle.evaluate_integer("_TC1", &[])? as u32,
+            tc2: self.handle.evaluate_integer("_TC2", &[])? as u32,
+            sampling_period: self.handle.evaluate_integer("_TSP", &[])? as u32,
+        })
+    }
+
+    /// Evaluates `_TZP`, the period in tenths of a second at which to poll the
+    /// temperature. `None` if the zone notifies about temperature changes instead.
+    pub fn polling_period(&self) -> Result<Option<u32>, ACPI_STATUS> {
+        match self.handle.evaluate_integer("_TZP", &[]) {
+            Ok(0) | Err(AE_NOT_FOUND) => Ok(None),
+            Ok(period) => Ok(Some(period as u32)),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Evaluates `_TZD`, the devices whose temperature the zone reports.
+    pub fn devices(&self) -> Result<Vec<Handle>, ACPI_STATUS> {
+        self.device_list("_TZD")
+    }
+
+    /// Calls `handler` with the notifications AML sends to the zone.
+    pub fn on_event<F>(&self, handler: F) -> Result<NotifySubscription, ACPI_STATUS>
+    where
+        F: Fn(ThermalZone, ThermalEvent) + Send + Sync + 'static,
+    {
+        self.handle
+            .on_notify(NotifyType::Device, move |handle, notify| {
+                handler(ThermalZone::new(handle), ThermalEvent::from_notify(notify))
+            })
+    }
+
+    /// Evaluates an optional temperature object.
+    fn temperature_object(&self, name: &str) -> Result<Option<Temperature>, ACPI_STATUS> {
+        match self.handle.evaluate_integer(name, &[]) {
+            Ok(value) => Ok(Some(Temperature(value as u32))),
+            Err(AE_NOT_FOUND) => Ok(None),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Evaluates an optional package of references.
+    fn device_list(&self, name: &str) -> Result<Vec<Handle>, ACPI_STATUS> {
+        match self.handle.evaluate_package(name, &[]) {
+            Ok(elements) => Ok(elements.iter().filter_map(Object::as_reference).collect()),
+            Err(AE_NOT_FOUND) => Ok(Vec::new()),
+            Err(status) => Err(status),
+        }
+    }
+}
+
+/// Turns the fans of a thermal zone on and off according to its active trip points.
+pub struct ActiveCooling {
+    zone: ThermalZone,
+    /// The fans of each active trip point, from `_AC0` downwards.
+    levels: Vec<(Temperature, Vec<usize>)>,
+    fans: Vec<DevicePower>,
+    running: Mutex<FanStates>,
+}
+
+/// The fans of an [`ActiveCooling`] that were turned on.
+struct FanStates {
+    running: Vec<bool>,
+    /// [`ActiveCooling::update`] is running. `_PSx`, `_ON` and `_OFF` usually `Sleep`, so
+    /// the lock isn't held meanwhile.
+    updating: bool,
+}
+
+impl ActiveCooling {
+    /// Reads the active trip points of `zone`.
+    ///
+    /// Fans listed by several trip points are shared between them. Fans that report being
+    /// on through `_PSC` or their `_PR0` resources stay on until [`ActiveCooling::update`]
+    /// finds them unneeded. Fans that can't report their state count as off.
+    pub fn new(zone: ThermalZone) -> Result<Self, ACPI_STATUS> {
+        let trip_points = zone.trip_points()?;
+        let mut devices: Vec<Handle> = Vec::new();
+        let mut levels = Vec::new();
+
+        for trip_point in trip_points.active {
+            let mut fans = Vec::new();
+
+            for device in trip_point.devices {
+                let index = match devices.iter().position(|&known| known == device) {
+                    Some(index) => index,
+                    None => {
+                        devices.push(device);
+                        devices.len() - 1
+                    }
+                };
+
+                fans.push(index);
+            }
+
+            levels.push((trip_point.temperature, fans));
+        }
+
+        let fans = devices
+            .into_iter()
+            .map(DevicePower::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Fans the firmware left on count as running, and take their power resource
+        // references so that update can turn them off
+        let running = fans
+            .iter()
+            .map(|fan| match fan.reported_state()? {
+                Some(DeviceState::D0) => fan.set_state(DeviceState::D0).map(|_| true),
+                _ => Ok(false),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let running = Mutex::new(FanStates {
+            running,
+            updating: false,
+        });
+
+        Ok(Self {
+            zone,
+            levels,
+            fans,
+            running,
+        })
+    }
+
+    pub fn zone(&self) -> ThermalZone {
+        self.zone
+    }
+
+    /// Turns on the fans of every trip point at or below `temperature` and turns off the
+    /// others.
+    ///
+    /// Waits while another processor updates the fans.
+    pub fn update(&self, temperature: Temperature) -> Result<(), ACPI_STATUS> {
+        let mut wanted = vec![false; self.fans.len()];
+
+        for (trip_point, fans) in &self.levels {
+            if temperature >= *trip_point {
+                for &fan in fans {
+                    wanted[fan] = true;
+                }
+            }
+        }
+
+        let mut running = self.begin_update();
+        let result = self.switch_fans(&wanted, &mut running);
+        self.end_update(running);
+
+        result
+    }
+
+    /// Marks the fans as being updated once no other update is running, returning which
+    /// of them run.
+    fn begin_update(&self) -> Vec<bool> {
+        loop {
+            let mut fans = self.running.lock();
+
+            if !fans.updating {
+                fans.updating = true;
+                return fans.running.clone();
+            }
+
+            drop(fans);
+            current_os_services_implementation()
+                .unwrap()
+                .sleep(FAN_UPDATE_WAIT);
+        }
+    }
+
+    /// Records which fans run after an update.
+    fn end_update(&self, running: Vec<bool>) {
+        let mut fans = self.running.lock();
+
+        fans.running = running;
+        fans.updating = false;
+    }
+
+    /// Turns the fans on or off to match `wanted`, updating `running` as they switch.
+    fn switch_fans(&self, wanted: &[bool], running: &mut [bool]) -> Result<(), ACPI_STATUS> {
+        // Turn fans on before turning others off, so cooling never drops in between
+        for on in [true, false] {
+            for (i, fan) in self.fans.iter().enumerate() {
+                if wanted[i] != on || running[i] == on {
+                    continue;
+                }
+
+                let state = match on {
+                    true => DeviceState::D0,
+                    false if fan.supports(DeviceState::D3Cold) => DeviceState::D3Cold,
+                    false => DeviceState::D3Hot,
+                };
+
+                fan.set_state(state)?;
+                running[i] = on;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads `_TMP` and updates the fans for it.
+    pub fn poll(&self) -> Result<Temperature, ACPI_STATUS> {
+        let temperature = self.zone.temperature()?;
+        self.update(temperature)?;
+
+        Ok(temperature)
+    }
+}
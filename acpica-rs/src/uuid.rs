@@ -0,0 +1,109 @@
+//! UUIDs as used by `_DSM`, `_OSC` and `ToUUID`.
+//!
+//! ACPI stores UUIDs in the mixed-endian layout of EFI GUIDs: the first three groups of the
+//! canonical string are little endian, the last two big endian. The conversion follows
+//! `utuuid.c`.
+
+use core::{fmt, str::FromStr};
+
+use acpica_sys::*;
+
+use crate::AE_BAD_PARAMETER;
+
+/// Length of the canonical string form, e.g. `a0b5b7c6-1318-441c-b0c9-fe695eaf949b`.
+const UUID_STRING_LENGTH: usize = 36;
+
+/// Offsets of the hyphens in the canonical string form.
+const UUID_HYPHEN_OFFSETS: [usize; 4] = [8, 13, 18, 23];
+
+/// For each byte of the binary form, the offset of its two hex digits in the string form.
+const UUID_BYTE_OFFSETS: [usize; 16] = [6, 4, 2, 0, 11, 9, 16, 14, 19, 21, 24, 26, 28, 30, 32, 34];
+
+/// A UUID in the byte order ACPI uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    /// Wraps a UUID that is already in the ACPI byte order.
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    /// Parses the canonical string form, in either case. Usable in constants:
+    ///
+    /// ```ignore
+    /// const PCI_HOST_BRIDGE: Uuid = Uuid::parse("33db4d5b-1ff7-401c-9657-7441c03dd766").unwrap();
+    /// ```
+    pub const fn parse(uuid: &str) -> Option<Self> {
+        let string = uuid.as_bytes();
+
+        if string.len() != UUID_STRING_LENGTH {
+            return None;
+        }
+
+        let mut i = 0;
+        while i < UUID_HYPHEN_OFFSETS.len() {
+            if string[UUID_HYPHEN_OFFSETS[i]] != b'-' {
+                return None;
+            }
+
+            i += 1;
+        }
+
+        let mut bytes = [0; 16];
+        let mut i = 0;
+        while i < bytes.len() {
+            let offset = UUID_BYTE_OFFSETS[i];
+
+            let (Some(high), Some(low)) =
+                (hex_digit(string[offset]), hex_digit(string[offset + 1]))
+            else {
+                return None;
+            };
+
+            bytes[i] = (high << 4) | low;
+            i += 1;
+        }
+
+        Some(Self(bytes))
+    }
+
+    /// The UUID in the ACPI byte order, as passed to `_DSM` and `_OSC`.
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = ACPI_STATUS;
+
+    fn from_str(uuid: &str) -> Result<Self, Self::Err> {
+        Self::parse(uuid).ok_or(AE_BAD_PARAMETER)
+    }
+}
+
+/// Formats the canonical string form in lower case.
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut string = [b'-'; UUID_STRING_LENGTH];
+
+        for (byte, &offset) in self.0.iter().zip(&UUID_BYTE_OFFSETS) {
+            string[offset] = HEX_DIGITS[(byte >> 4) as usize];
+            string[offset + 1] = HEX_DIGITS[(byte & 0xF) as usize];
+        }
+
+        // Only ASCII was written
+        f.write_str(core::str::from_utf8(&string).unwrap())
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+const fn hex_digit(character: u8) -> Option<u8> {
+    match character {
+        b'0'..=b'9' => Some(character - b'0'),
+        b'a'..=b'f' => Some(character - b'a' + 10),
+        b'A'..=b'F' => Some(character - b'A' + 10),
+        _ => None,
+    }
+}
@@ -0,0 +1,175 @@
+//! Device wake configuration.
+//!
+//! A device that can wake the system has a `_PRW` object naming its wake GPE, the deepest
+//! sleep state it can wake the system from and the power resources it needs to do so. Arming
+//! it takes three steps: taking references to those power resources, telling the device
+//! through `_DSW` (or the older `_PSW`) and enabling the GPE for wake.
+
+use acpica_sys::*;
+use alloc::vec::Vec;
+
+use crate::{
+    device_power::{acquire_all, power_resources, release_all, PowerResource},
+    gpe::Gpe,
+    namespace::{Handle, Object},
+    power::{DeviceState, SleepState},
+    AE_BAD_PARAMETER, AE_NOT_FOUND, AE_TYPE,
+};
+
+/// The wake capabilities of a device, from its `_PRW`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WakeInfo {
+    pub gpe: Gpe,
+    /// The deepest sleep state the device can wake the system from.
+    pub deepest_sleep_state: SleepState,
+    /// Power resources that have to be on for the device to signal wake.
+    pub power_resources: Vec<Handle>,
+}
+
+impl WakeInfo {
+    /// Evaluates `_PRW` of `device`.
+    pub fn get(device: Handle) -> Result<Self, ACPI_STATUS> {
+        let prw = device.evaluate_package("_PRW", &[])?;
+
+        let [event, sleep_state, resources @ ..] = prw.as_slice() else {
+            return Err(AE_TYPE);
+        };
+
+        // Either a GPE of the FADT blocks, or a reference to a GPE block device and an index
+        let gpe = match event {
+            Object::Integer(number) => Gpe::new(*number as u32),
+            Object::Package(elements) => match elements.as_slice() {
+                [Object::Reference(block), Object::Integer(number)] => Gpe {
+                    device: Some(*block),
+                    number: *number as u32,
+                },
+                _ => return Err(AE_TYPE),
+            },
+            _ => return Err(AE_TYPE),
+        };
+
+        let deepest_sleep_state = sleep_state
+            .as_integer()
+            .and_then(|state| SleepState::ALL.get(state as usize).copied())
+            .ok_or(AE_TYPE)?;
+
+        let power_resources = resources.iter().filter_map(Object::as_reference).collect();
+
+        Ok(Self {
+            gpe,
+            deepest_sleep_state,
+            power_resources,
+        })
+    }
+}
+
+/// A device that can wake the system.
+pub struct WakeDevice {
+    device: Handle,
+    info: WakeInfo,
+    /// The power resources of `info`, sorted by resource order.
+    resources: Vec<PowerResource>,
+}
+
+impl WakeDevice {
+    /// Reads the `_PRW` of `device` and sets its GPE up for wake.
+    ///
+    /// Run [`gpe::update_all`](crate::gpe::update_all) once all wake devices are set up.
+    pub fn new(device: Handle) -> Result<Self, ACPI_STATUS> {
+        let info = WakeInfo::get(device)?;
+        let resources = power_resources(&info.power_resources)?;
+        info.gpe.setup_for_wake(device)?;
+
+        Ok(Self {
+            device,
+            info,
+            resources,
+        })
+    }
+
+    pub fn device(&self) -> Handle {
+        self.device
+    }
+
+    pub fn info(&self) -> &WakeInfo {
+        &self.info
+    }
+
+    /// Arms the device to wake the system from `system_state`, or to signal runtime wake
+    /// events for [`SleepState::S0`], while the device is in `device_state`.
+    pub fn enable(
+        &self,
+        system_state: SleepState,
+        device_state: DeviceState,
+    ) -> Result<(), ACPI_STATUS> {
+        if system_state > self.info.deepest_sleep_state {
+            return Err(AE_BAD_PARAMETER);
+        }
+
+        acquire_all(&self.resources)?;
+
+        if let Err(status) = self.set_device_wake(true, system_state, device_state) {
+            let _ = release_all(&self.resources);
+            return Err(status);
+        }
+
+        // Runtime wake uses the GPE like any other event, system wake through the wake mask
+        let result = if system_state == SleepState::S0 {
+            self.info.gpe.enable()
+        } else {
+            self.info.gpe.set_wake_mask(true)
+        };
+
+        if let Err(status) = result {
+            let _ = self.set_device_wake(false, system_state, DeviceState::D0);
+            let _ = release_all(&self.resources);
+            return Err(status);
+        }
+
+        Ok(())
+    }
+
+    /// Disarms the device again after [`WakeDevice::enable`] with the same `system_state`.
+    pub fn disable(&self, system_state: SleepState) -> Result<(), ACPI_STATUS> {
+        if system_state == SleepState::S0 {
+            self.info.gpe.disable()?;
+        } else {
+            self.info.gpe.set_wake_mask(false)?;
+        }
+
+        self.set_device_wake(false, system_state, DeviceState::D0)?;
+
+        release_all(&self.resources)
+    }
+
+    /// Calls `_DSW`, or `_PSW` if the device doesn't have it. Devices with neither are
+    /// always armed.
+    fn set_device_wake(
+        &self,
+        enable: bool,
+        system_state: SleepState,
+        device_state: DeviceState,
+    ) -> Result<(), ACPI_STATUS> {
+        let dsw = self.device.evaluate(
+            "_DSW",
+            &[
+                Object::Integer(enable as u64),
+                Object::Integer(system_state.id() as u64),
+                Object::Integer(device_state.aml_id() as u64),
+            ],
+        );
+
+        match dsw {
+            Err(AE_NOT_FOUND) => {}
+            result => return result.map(|_| ()),
+        }
+
+        match self
+            .device
+            .evaluate("_PSW", &[Object::Integer(enable as u64)])
+        {
+            Ok(_) | Err(AE_NOT_FOUND) => Ok(()),
+            Err(status) => Err(status),
+        }
+    }
+}
@@ -3,7 +3,7 @@
 #![allow(non_snake_case)]
 #![no_std]
 
-use core::ffi::c_void;
+use core::ffi::{c_char, c_void};
 use core::include;
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
@@ -18,5 +18,56 @@ extern "C" {
     pub fn AcpiWalkNamespace(Type: ACPI_OBJECT_TYPE, StartObject: ACPI_HANDLE, MaxDepth: UINT32, DescendingCallback: ACPI_WALK_CALLBACK, AscendingCallback: ACPI_WALK_CALLBACK, Context: *mut c_void, ReturnValue: *mut *mut c_void) -> ACPI_STATUS;
     pub fn AcpiGetCurrentResources(Device: ACPI_HANDLE, RetBuffer: *mut ACPI_BUFFER) -> ACPI_STATUS;
     pub fn AcpiGetObjectInfo(Object: ACPI_HANDLE, ReturnBuffer: *mut *mut ACPI_DEVICE_INFO) -> ACPI_STATUS;
+    pub fn AcpiGetTable(Signature: ACPI_STRING, Instance: UINT32, OutTable: *mut *mut ACPI_TABLE_HEADER) -> ACPI_STATUS;
+    pub fn AcpiPutTable(Table: *mut ACPI_TABLE_HEADER);
+    pub fn AcpiInstallAddressSpaceHandler(Device: ACPI_HANDLE, SpaceId: ACPI_ADR_SPACE_TYPE, Handler: ACPI_ADR_SPACE_HANDLER, Setup: ACPI_ADR_SPACE_SETUP, Context: *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiRemoveAddressSpaceHandler(Device: ACPI_HANDLE, SpaceId: ACPI_ADR_SPACE_TYPE, Handler: ACPI_ADR_SPACE_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiRead(Value: *mut UINT64, Reg: *mut ACPI_GENERIC_ADDRESS) -> ACPI_STATUS;
+    pub fn AcpiWrite(Value: UINT64, Reg: *mut ACPI_GENERIC_ADDRESS) -> ACPI_STATUS;
+    pub fn AcpiGetDevices(HID: *mut c_char, UserFunction: ACPI_WALK_CALLBACK, Context: *mut c_void, ReturnValue: *mut *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiGetHandle(Parent: ACPI_HANDLE, Pathname: *const c_char, RetHandle: *mut ACPI_HANDLE) -> ACPI_STATUS;
+    pub fn AcpiGetName(Object: ACPI_HANDLE, NameType: UINT32, RetPathPtr: *mut ACPI_BUFFER) -> ACPI_STATUS;
+    pub fn AcpiGetParent(Object: ACPI_HANDLE, OutHandle: *mut ACPI_HANDLE) -> ACPI_STATUS;
+    pub fn AcpiGetType(Object: ACPI_HANDLE, OutType: *mut ACPI_OBJECT_TYPE) -> ACPI_STATUS;
+    pub fn AcpiEvaluateObject(Object: ACPI_HANDLE, Pathname: ACPI_STRING, ParameterObjects: *mut ACPI_OBJECT_LIST, ReturnObjectBuffer: *mut ACPI_BUFFER) -> ACPI_STATUS;
+    pub fn AcpiInstallGpeHandler(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32, Type: UINT32, Address: ACPI_GPE_HANDLER, Context: *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiRemoveGpeHandler(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32, Address: ACPI_GPE_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiEnableGpe(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiDisableGpe(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiSetGpe(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32, Action: UINT8) -> ACPI_STATUS;
+    pub fn AcpiClearGpe(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiFinishGpe(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiGetGpeStatus(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32, EventStatus: *mut ACPI_EVENT_STATUS) -> ACPI_STATUS;
+    pub fn AcpiSetupGpeForWake(ParentDevice: ACPI_HANDLE, GpeDevice: ACPI_HANDLE, GpeNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiSetGpeWakeMask(GpeDevice: ACPI_HANDLE, GpeNumber: UINT32, Action: UINT8) -> ACPI_STATUS;
+    pub fn AcpiUpdateAllGpes() -> ACPI_STATUS;
+    pub fn AcpiInstallGpeBlock(GpeDevice: ACPI_HANDLE, GpeBlockAddress: *mut ACPI_GENERIC_ADDRESS, RegisterCount: UINT32, InterruptNumber: UINT32) -> ACPI_STATUS;
+    pub fn AcpiRemoveGpeBlock(GpeDevice: ACPI_HANDLE) -> ACPI_STATUS;
+    pub fn AcpiInstallFixedEventHandler(AcpiEvent: UINT32, Handler: ACPI_EVENT_HANDLER, Context: *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiRemoveFixedEventHandler(AcpiEvent: UINT32, Handler: ACPI_EVENT_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiEnableEvent(Event: UINT32, Flags: UINT32) -> ACPI_STATUS;
+    pub fn AcpiDisableEvent(Event: UINT32, Flags: UINT32) -> ACPI_STATUS;
+    pub fn AcpiClearEvent(Event: UINT32) -> ACPI_STATUS;
+    pub fn AcpiGetEventStatus(Event: UINT32, EventStatus: *mut ACPI_EVENT_STATUS) -> ACPI_STATUS;
+    pub fn AcpiInstallNotifyHandler(Device: ACPI_HANDLE, HandlerType: UINT32, Handler: ACPI_NOTIFY_HANDLER, Context: *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiRemoveNotifyHandler(Device: ACPI_HANDLE, HandlerType: UINT32, Handler: ACPI_NOTIFY_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiGetSleepTypeData(SleepState: UINT8, Slp_TypA: *mut UINT8, Slp_TypB: *mut UINT8) -> ACPI_STATUS;
+    pub fn AcpiEnterSleepStatePrep(SleepState: UINT8) -> ACPI_STATUS;
+    pub fn AcpiEnterSleepState(SleepState: UINT8) -> ACPI_STATUS;
+    pub fn AcpiLeaveSleepStatePrep(SleepState: UINT8) -> ACPI_STATUS;
+    pub fn AcpiLeaveSleepState(SleepState: UINT8) -> ACPI_STATUS;
+    pub fn AcpiSetFirmwareWakingVector(PhysicalAddress: ACPI_PHYSICAL_ADDRESS, PhysicalAddress64: ACPI_PHYSICAL_ADDRESS) -> ACPI_STATUS;
+    pub fn AcpiReset() -> ACPI_STATUS;
+    pub fn AcpiInstallInterface(InterfaceName: ACPI_STRING) -> ACPI_STATUS;
+    pub fn AcpiRemoveInterface(InterfaceName: ACPI_STRING) -> ACPI_STATUS;
+    pub fn AcpiUpdateInterfaces(Action: UINT8) -> ACPI_STATUS;
+    pub fn AcpiInstallInterfaceHandler(Handler: ACPI_INTERFACE_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiInstallTable(Table: *mut ACPI_TABLE_HEADER) -> ACPI_STATUS;
+    pub fn AcpiLoadTable(Table: *mut ACPI_TABLE_HEADER, TableIdx: *mut UINT32) -> ACPI_STATUS;
+    pub fn AcpiUnloadTable(TableIndex: UINT32) -> ACPI_STATUS;
+    pub fn AcpiUnloadParentTable(Object: ACPI_HANDLE) -> ACPI_STATUS;
+    pub fn AcpiInstallTableHandler(Handler: ACPI_TABLE_HANDLER, Context: *mut c_void) -> ACPI_STATUS;
+    pub fn AcpiRemoveTableHandler(Handler: ACPI_TABLE_HANDLER) -> ACPI_STATUS;
+    pub fn AcpiAcquireGlobalLock(Timeout: UINT16, Handle: *mut UINT32) -> ACPI_STATUS;
+    pub fn AcpiReleaseGlobalLock(Handle: UINT32) -> ACPI_STATUS;
 }
-